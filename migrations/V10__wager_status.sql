@@ -0,0 +1,2 @@
+-- Cancelled wagers are refunded and take no part in their draws
+ALTER TABLE wager ADD COLUMN status VARCHAR(32) NOT NULL DEFAULT 'Active';
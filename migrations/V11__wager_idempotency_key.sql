@@ -0,0 +1,5 @@
+-- Client supplied key of the request that placed a wager, unique per player, and the SHA-256
+-- of the request to tell a retry from a different request reusing the key
+ALTER TABLE wager ADD COLUMN idempotency_key VARCHAR(255);
+ALTER TABLE wager ADD COLUMN request_hash BYTEA;
+CREATE UNIQUE INDEX idx_wager_user_idempotency_key ON wager (user_id, idempotency_key) WHERE idempotency_key IS NOT NULL;
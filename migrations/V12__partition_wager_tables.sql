@@ -0,0 +1,116 @@
+-- Partition the wager tables per draw. draw_wager and win are partitioned by their draw,
+-- board and selection by the last draw of their wager, i.e. the draw after which the wager
+-- can be deleted. Partitions are created with each draw and dropped by the retention job.
+
+-- Wagers without draws have no partition to store their boards in. They are left for an
+-- operator to resolve rather than deleted with their payments.
+DO $$
+DECLARE
+    orphans BIGINT;
+BEGIN
+    SELECT COUNT(*) INTO orphans FROM wager w WHERE NOT EXISTS (SELECT 1 FROM draw_wager dw WHERE dw.wager_id = w.id);
+    IF orphans > 0 THEN
+        RAISE EXCEPTION '% wagers have no draws and cannot be partitioned', orphans
+            USING HINT = 'Find them with: SELECT * FROM wager w WHERE NOT EXISTS (SELECT 1 FROM draw_wager dw WHERE dw.wager_id = w.id)';
+    END IF;
+END $$;
+
+ALTER TABLE wager ADD COLUMN last_draw_id INTEGER REFERENCES draw(id);
+UPDATE wager w SET last_draw_id = (
+    SELECT d.id FROM draw_wager dw JOIN draw d ON d.id = dw.draw_id
+    WHERE dw.wager_id = w.id
+    ORDER BY COALESCE(d.draw_time, d.close_time) DESC, d.id DESC
+    LIMIT 1
+);
+ALTER TABLE wager ALTER COLUMN last_draw_id SET NOT NULL;
+CREATE INDEX idx_wager_last_draw_id ON wager (last_draw_id);
+
+ALTER TABLE draw_wager RENAME TO draw_wager_unpartitioned;
+ALTER TABLE win RENAME TO win_unpartitioned;
+ALTER TABLE board RENAME TO board_unpartitioned;
+ALTER TABLE selection RENAME TO selection_unpartitioned;
+
+CREATE TABLE draw_wager (
+    draw_id INTEGER NOT NULL,
+    wager_id UUID NOT NULL
+) PARTITION BY LIST (draw_id);
+
+CREATE TABLE board (
+    id UUID NOT NULL,
+    wager_id UUID NOT NULL,
+    last_draw_id INTEGER NOT NULL,
+    game_type VARCHAR(255) NOT NULL DEFAULT 'NORMAL',
+    system_game_level INTEGER,
+    stake INTEGER NOT NULL DEFAULT 0
+) PARTITION BY LIST (last_draw_id);
+
+CREATE TABLE selection (
+    id UUID NOT NULL,
+    board_id UUID NOT NULL,
+    last_draw_id INTEGER NOT NULL,
+    name VARCHAR(20) NOT NULL,
+    values INTEGER[] NOT NULL,
+    machine_picked BOOLEAN[] NOT NULL DEFAULT '{}'
+) PARTITION BY LIST (last_draw_id);
+
+CREATE TABLE win (
+    id UUID NOT NULL,
+    wager_id UUID NOT NULL,
+    draw_id INTEGER NOT NULL,
+    board_id UUID NOT NULL,
+    win_class_id UUID NOT NULL,
+    amount BIGINT NOT NULL DEFAULT 0,
+    winning_rows INTEGER NOT NULL DEFAULT 1,
+    row_stake BIGINT NOT NULL DEFAULT 0
+) PARTITION BY LIST (draw_id);
+
+DO $$
+DECLARE
+    draw_id INTEGER;
+    partitioned TEXT;
+BEGIN
+    FOR draw_id IN SELECT id FROM draw LOOP
+        FOREACH partitioned IN ARRAY ARRAY['draw_wager', 'board', 'selection', 'win'] LOOP
+            EXECUTE format('CREATE TABLE %I PARTITION OF %I FOR VALUES IN (%s)', partitioned || '_d' || draw_id, partitioned, draw_id);
+        END LOOP;
+    END LOOP;
+END $$;
+
+INSERT INTO draw_wager (draw_id, wager_id) SELECT draw_id, wager_id FROM draw_wager_unpartitioned;
+INSERT INTO board (id, wager_id, last_draw_id, game_type, system_game_level, stake)
+    SELECT b.id, b.wager_id, w.last_draw_id, b.game_type, b.system_game_level, b.stake
+    FROM board_unpartitioned b JOIN wager w ON w.id = b.wager_id;
+INSERT INTO selection (id, board_id, last_draw_id, name, values, machine_picked)
+    SELECT s.id, s.board_id, b.last_draw_id, s.name, s.values, s.machine_picked
+    FROM selection_unpartitioned s JOIN board b ON b.id = s.board_id;
+INSERT INTO win (id, wager_id, draw_id, board_id, win_class_id, amount, winning_rows, row_stake)
+    SELECT id, wager_id, draw_id, board_id, win_class_id, amount, winning_rows, row_stake FROM win_unpartitioned;
+
+DROP TABLE win_unpartitioned;
+DROP TABLE selection_unpartitioned;
+DROP TABLE board_unpartitioned;
+DROP TABLE draw_wager_unpartitioned;
+
+-- Keys of partitioned tables include the partition key. A win refers to its board by id only,
+-- as the board may be in the partition of another draw.
+ALTER TABLE draw_wager ADD FOREIGN KEY (draw_id) REFERENCES draw(id);
+ALTER TABLE draw_wager ADD FOREIGN KEY (wager_id) REFERENCES wager(id);
+CREATE INDEX idx_draw_wager_draw_id_wager_id ON draw_wager (draw_id, wager_id);
+CREATE INDEX idx_draw_wager_wager_id ON draw_wager (wager_id);
+
+ALTER TABLE board ADD PRIMARY KEY (id, last_draw_id);
+ALTER TABLE board ADD FOREIGN KEY (wager_id) REFERENCES wager(id) ON DELETE CASCADE;
+CREATE INDEX idx_board_wager_id ON board (wager_id);
+
+ALTER TABLE selection ADD PRIMARY KEY (id, last_draw_id);
+ALTER TABLE selection ADD FOREIGN KEY (board_id, last_draw_id) REFERENCES board(id, last_draw_id) ON DELETE CASCADE;
+CREATE INDEX idx_selection_board_id ON selection (board_id);
+
+ALTER TABLE win ADD PRIMARY KEY (id, draw_id);
+ALTER TABLE win ADD FOREIGN KEY (wager_id) REFERENCES wager(id) ON DELETE CASCADE;
+ALTER TABLE win ADD FOREIGN KEY (draw_id) REFERENCES draw(id);
+ALTER TABLE win ADD FOREIGN KEY (win_class_id) REFERENCES win_class(id);
+CREATE INDEX idx_win_draw_id_win_class_id ON win (draw_id, win_class_id);
+CREATE INDEX idx_win_wager_id ON win (wager_id);
+CREATE INDEX idx_win_board_id ON win (board_id);
+CREATE INDEX idx_win_win_class_id ON win (win_class_id);
@@ -0,0 +1,16 @@
+-- Hit pattern of a win class: the number of hits required on each listed draw level.
+-- Classes are evaluated in rank order and a row wins only in the first matching class.
+ALTER TABLE win_class ADD COLUMN rank INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE win_class ADD COLUMN draw_levels VARCHAR(255)[] NOT NULL DEFAULT '{}';
+ALTER TABLE win_class ADD COLUMN hits INTEGER[] NOT NULL DEFAULT '{}';
+
+-- A win is recorded per draw and board; system boards may win with several rows in the same class.
+ALTER TABLE win ADD COLUMN draw_id INTEGER NOT NULL REFERENCES draw(id);
+ALTER TABLE win ADD COLUMN board_id UUID NOT NULL REFERENCES board(id) ON DELETE CASCADE;
+ALTER TABLE win ADD COLUMN winning_rows INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE win ALTER COLUMN amount SET DEFAULT 0;
+CREATE INDEX idx_win_draw_id_win_class_id ON win (draw_id, win_class_id);
+CREATE INDEX idx_win_board_id ON win (board_id);
+
+-- Keyset pagination over the wagers of a draw
+CREATE INDEX idx_draw_wager_draw_id_wager_id ON draw_wager (draw_id, wager_id);
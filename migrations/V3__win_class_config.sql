@@ -0,0 +1,3 @@
+-- Win classes are upserted from configuration on startup. Classes removed from the
+-- configuration are kept for existing wins but no longer used in winset calculation.
+ALTER TABLE win_class ADD COLUMN active BOOLEAN NOT NULL DEFAULT TRUE;
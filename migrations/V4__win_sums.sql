@@ -0,0 +1,12 @@
+-- Win sums are integers in the smallest currency unit
+ALTER TABLE win ALTER COLUMN amount TYPE BIGINT USING amount::BIGINT;
+-- Stake of a single winning row, used by factor based win classes
+ALTER TABLE win ADD COLUMN row_stake BIGINT NOT NULL DEFAULT 0;
+
+-- Win class totals set externally for a draw, shared by the winners of the class
+CREATE TABLE external_win_total (
+    draw_id INTEGER NOT NULL REFERENCES draw(id),
+    win_class_id UUID NOT NULL REFERENCES win_class(id),
+    amount BIGINT NOT NULL,
+    PRIMARY KEY (draw_id, win_class_id)
+);
@@ -0,0 +1,4 @@
+-- Stake of a board in one draw; the wager stake is the sum of its board stakes
+ALTER TABLE board ADD COLUMN stake INTEGER NOT NULL DEFAULT 0;
+UPDATE board b SET stake = w.stake / (SELECT COUNT(*) FROM board WHERE wager_id = w.id)
+FROM wager w WHERE w.id = b.wager_id;
@@ -0,0 +1,2 @@
+-- Whether each selected value was machine-picked, in the same order as the values
+ALTER TABLE selection ADD COLUMN machine_picked BOOLEAN[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,2 @@
+-- Winning numbers per draw level, as a JSON array of {"draw_level_id", "numbers"} objects
+ALTER TABLE draw ADD COLUMN winning_numbers JSONB NOT NULL DEFAULT '[]';
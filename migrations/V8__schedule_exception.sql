@@ -0,0 +1,8 @@
+-- Schedule exceptions added at runtime through the Admin API, on top of those in the game configuration
+CREATE TABLE schedule_exception (
+    id UUID PRIMARY KEY,
+    game_id UUID NOT NULL REFERENCES game(id),
+    exception JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+CREATE INDEX idx_schedule_exception_game_id ON schedule_exception (game_id);
@@ -0,0 +1,10 @@
+-- Stake refunded to a wager for a cancelled draw, in the smallest currency unit
+CREATE TABLE refund (
+    id UUID PRIMARY KEY,
+    wager_id UUID NOT NULL REFERENCES wager(id),
+    draw_id INTEGER NOT NULL REFERENCES draw(id),
+    amount BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (wager_id, draw_id)
+);
+CREATE INDEX idx_refund_draw_id ON refund (draw_id);
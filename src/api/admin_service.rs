@@ -1,26 +1,271 @@
+use std::sync::Arc;
+use deadpool_postgres::Object;
 use tonic::{Request, Response, Status};
+use uuid::Uuid;
+use tracing::{info, error};
+use crate::config::app_config::{Config, ScheduleExceptionConfig};
+use crate::core::draw::{DrawStatus, WinningNumbers};
+use crate::core::draw_level::DrawLevel;
+use crate::core::draw_manager::DrawManager;
+use crate::core::extension::Extensions;
+use crate::core::{schedule, win_sum};
+use crate::db::{self, Pool};
 
 pub mod admin {
     tonic::include_proto!("admin");
 }
 
-use admin::{admin_server::Admin, ReceiveExternalDrawNumbersRequest, ReceiveExternalDrawNumbersResponse};
+use admin::{
+    add_schedule_exception_request::Exception,
+    admin_server::Admin,
+    AddScheduleExceptionRequest,
+    AddScheduleExceptionResponse,
+    CancelDrawRequest,
+    CancelDrawResponse,
+    ReceiveExternalDrawNumbersRequest,
+    ReceiveExternalDrawNumbersResponse,
+    SetExternalWinTotalRequest,
+    SetExternalWinTotalResponse,
+};
 
-pub struct AdminService;
+pub struct AdminService {
+    pool: Pool,
+    config: Arc<Config>,
+    extensions: Arc<Extensions>,
+}
+
+impl AdminService {
+    pub fn new(pool: Pool, config: Arc<Config>, extensions: Arc<Extensions>) -> Self {
+        AdminService { pool, config, extensions }
+    }
+
+    /// Gets a pooled database connection. Transactions are opened on the plain client it
+    /// dereferences to, `&mut tokio_postgres::Client`, so that they work with the generic
+    /// database functions.
+    async fn client(&self) -> Result<Object, Status> {
+        self.pool.get().await.map_err(|e| {
+            error!("Failed to get a database connection: {}", e);
+            Status::unavailable(format!("Failed to get a database connection: {}", e))
+        })
+    }
+}
 
 #[tonic::async_trait]
 impl Admin for AdminService {
+    /// Sets the winning numbers of a closed draw of an externally drawn game, moves the draw
+    /// to Drawn and starts its winset calculation.
     async fn receive_external_draw_numbers(
         &self,
         request: Request<ReceiveExternalDrawNumbersRequest>,
     ) -> Result<Response<ReceiveExternalDrawNumbersResponse>, Status> {
-        println!("Received external draw numbers: {:?}", request);
+        info!("Received external draw numbers: {:?}", request);
+        let request = request.into_inner();
 
-        // TODO: Implement logic to update the draw with external numbers
+        let mut winning_numbers = Vec::with_capacity(request.winning_numbers.len());
+        for numbers in request.winning_numbers {
+            let Some(draw_level_id) = numbers.draw_level_id else {
+                return Err(Status::invalid_argument("Missing draw_level_id"));
+            };
+            let draw_level_id = Uuid::parse_str(&draw_level_id.value)
+                .map_err(|e| Status::invalid_argument(format!("Invalid draw_level_id UUID: {}", e)))?;
+            if let Some(n) = numbers.numbers.iter().find(|n| **n < 0) {
+                return Err(Status::invalid_argument(format!("Invalid winning number {}", n)));
+            }
+            let numbers = numbers.numbers.iter().map(|n| *n as u32).collect();
+            winning_numbers.push(WinningNumbers { draw_level_id, numbers });
+        }
+
+        let mut client = self.client().await?;
+        let mut draw = db::draw::get_draw(&client, request.draw_id)
+            .await
+            .map_err(|e| {
+                error!("Failed to get draw {}: {}", request.draw_id, e);
+                Status::internal(format!("Failed to get draw: {}", e))
+            })?
+            .ok_or_else(|| Status::not_found(format!("Draw {} not found", request.draw_id)))?;
+        let Some(game_config) = self.config.find_game(draw.game_id) else {
+            return Err(Status::failed_precondition(format!("Game {} of draw {} is not configured", draw.game_id, draw.id)));
+        };
+        if !game_config.externally_drawn {
+            return Err(Status::failed_precondition(format!("Game '{}' is not externally drawn", game_config.name)));
+        }
+        if draw.status != DrawStatus::Closed {
+            return Err(Status::failed_precondition(format!("Draw {} is {:?}, expected Closed", draw.id, draw.status)));
+        }
+        let draw_levels: Vec<DrawLevel> = game_config
+            .draw_levels
+            .iter()
+            .map(|level| DrawLevel::from_config(draw.game_id, level))
+            .collect();
+        DrawManager::validate_winning_numbers(&draw_levels, &winning_numbers).map_err(Status::invalid_argument)?;
+
+        draw.winning_numbers = winning_numbers;
+        DrawManager::persist_transition(&mut client, &self.extensions, &mut draw, DrawStatus::Drawn)
+            .await
+            .map_err(|e| {
+                error!("Failed to transition draw {} to Drawn: {}", draw.id, e);
+                Status::failed_precondition(format!("Failed to transition draw {} to Drawn: {}", draw.id, e))
+            })?;
+        info!("Draw {} drawn with external numbers: {:?}", draw.id, draw.winning_numbers);
+
+        let draw_id = draw.id;
+        DrawManager::spawn_winset_calculation(self.pool.clone(), self.extensions.clone(), draw, draw_levels);
 
         let reply = ReceiveExternalDrawNumbersResponse {
             success: true,
-            message: "External draw numbers received successfully.".to_string(),
+            message: format!("Winning numbers of draw {} received, winset calculation started.", draw_id),
+        };
+        Ok(Response::new(reply))
+    }
+
+    /// Adds an exception to the draw schedule of the game and plans the draws that have not
+    /// opened yet again.
+    async fn add_schedule_exception(
+        &self,
+        request: Request<AddScheduleExceptionRequest>,
+    ) -> Result<Response<AddScheduleExceptionResponse>, Status> {
+        info!("Received schedule exception: {:?}", request);
+        let request = request.into_inner();
+        let requested_game_id = request
+            .game_id
+            .map(|game_id| Uuid::parse_str(&game_id.value))
+            .transpose()
+            .map_err(|e| Status::invalid_argument(format!("Invalid game_id UUID: {}", e)))?;
+        let game_config = self.config.resolve_game(requested_game_id).map_err(Status::invalid_argument)?;
+
+        let exception = match request.exception {
+            Some(Exception::Skip(skip)) => ScheduleExceptionConfig::Skip { date: skip.date, time: skip.time },
+            Some(Exception::Moved(moved)) => ScheduleExceptionConfig::Move { date: moved.date, time: moved.time, to: moved.to },
+            Some(Exception::Extra(extra)) => ScheduleExceptionConfig::Extra {
+                draw_time: extra.draw_time,
+                open_time: extra.open_time,
+                close_time: extra.close_time,
+            },
+            None => return Err(Status::invalid_argument("Missing schedule exception")),
+        };
+
+        schedule::validate_exception(&exception, game_config.timezone).map_err(Status::invalid_argument)?;
+        let game_id = Uuid::parse_str(&game_config.id).map_err(|e| Status::internal(format!("Invalid game ID in config: {}", e)))?;
+
+        let mut client = self.client().await?;
+        let client: &mut tokio_postgres::Client = &mut client;
+        let internal = |e: tokio_postgres::Error| {
+            error!("Failed to add schedule exception: {}", e);
+            Status::internal(format!("Failed to add schedule exception: {}", e))
+        };
+        let transaction = client.transaction().await.map_err(internal)?;
+        db::schedule_exception::lock_game_schedule(&transaction, game_id).await.map_err(internal)?;
+        db::schedule_exception::insert_schedule_exception(&transaction, game_id, &exception).await.map_err(internal)?;
+        let deleted = db::draw::delete_created_draws(&transaction, game_id).await.map_err(internal)?;
+        transaction.commit().await.map_err(internal)?;
+        for draw_id in &deleted {
+            if let Err(e) = db::partition::drop_draw_partitions(client, *draw_id).await {
+                error!("Failed to drop partitions of deleted draw {}: {}", draw_id, e);
+            }
+        }
+
+        let created = DrawManager::plan_draws(client, game_config).await.map_err(|e| {
+            error!("Failed to plan draws after adding a schedule exception: {}", e);
+            Status::internal(format!("Schedule exception added, but planning draws failed: {}", e))
+        })?;
+        info!("Added schedule exception {:?}, replaced {} created draws with {} new draws", exception, deleted.len(), created);
+
+        let reply = AddScheduleExceptionResponse {
+            success: true,
+            message: format!("Schedule exception added, {} draws planned again.", created),
+            replanned_draws: created,
+        };
+        Ok(Response::new(reply))
+    }
+
+    /// Cancels a draw that is not finalized and refunds the participating wagers their stake
+    /// for the draw. For a cancelled draw, refunds the wagers not refunded yet.
+    async fn cancel_draw(
+        &self,
+        request: Request<CancelDrawRequest>,
+    ) -> Result<Response<CancelDrawResponse>, Status> {
+        info!("Received draw cancellation: {:?}", request);
+        let request = request.into_inner();
+
+        let mut client = self.client().await?;
+        let mut draw = db::draw::get_draw(&client, request.draw_id)
+            .await
+            .map_err(|e| {
+                error!("Failed to get draw {}: {}", request.draw_id, e);
+                Status::internal(format!("Failed to get draw: {}", e))
+            })?
+            .ok_or_else(|| Status::not_found(format!("Draw {} not found", request.draw_id)))?;
+        // Cancelling a cancelled draw again completes its refunds, e.g. after a failure
+        let refunded = match draw.status {
+            DrawStatus::Finalized => {
+                return Err(Status::failed_precondition(format!("Draw {} is Finalized and cannot be cancelled", draw.id)));
+            },
+            DrawStatus::Cancelled => DrawManager::refund_draw(&mut client, &self.extensions, &draw).await,
+            _ => DrawManager::cancel_draw(&mut client, &self.extensions, &mut draw).await,
+        }
+        .map_err(|e| {
+            error!("Failed to cancel draw {}: {}", draw.id, e);
+            Status::failed_precondition(format!("Failed to cancel draw {}: {}", draw.id, e))
+        })?;
+        info!("Draw {} cancelled, refunded {} wagers", draw.id, refunded);
+
+        let reply = CancelDrawResponse {
+            success: true,
+            message: format!("Draw {} cancelled, {} wagers refunded.", draw.id, refunded),
+            refunded_wagers: refunded,
+        };
+        Ok(Response::new(reply))
+    }
+
+    /// Sets the total win sum of an external win class in a draw with a calculated winset.
+    /// The draw is confirmed on the next draw management tick once no external win class
+    /// with winners is missing its total.
+    async fn set_external_win_total(
+        &self,
+        request: Request<SetExternalWinTotalRequest>,
+    ) -> Result<Response<SetExternalWinTotalResponse>, Status> {
+        info!("Received external win total: {:?}", request);
+        let request = request.into_inner();
+        let Some(win_class_id) = request.win_class_id else {
+            return Err(Status::invalid_argument("Missing win_class_id"));
+        };
+        let win_class_id = Uuid::parse_str(&win_class_id.value)
+            .map_err(|e| Status::invalid_argument(format!("Invalid win_class_id UUID: {}", e)))?;
+        if request.amount > i64::MAX as u64 {
+            return Err(Status::invalid_argument(format!("Amount {} is too large", request.amount)));
+        }
+
+        let mut client = self.client().await?;
+        let internal = |e: tokio_postgres::Error| {
+            error!("Failed to set external win total: {}", e);
+            Status::internal(format!("Failed to set external win total: {}", e))
+        };
+        let mut draw = db::draw::get_draw(&client, request.draw_id)
+            .await
+            .map_err(internal)?
+            .ok_or_else(|| Status::not_found(format!("Draw {} not found", request.draw_id)))?;
+        let win_class = db::win_class::get_win_classes(&client, draw.game_id)
+            .await
+            .map_err(internal)?
+            .into_iter()
+            .find(|c| c.id == win_class_id)
+            .ok_or_else(|| Status::not_found(format!("Win class {} not found for the game of draw {}", win_class_id, draw.id)))?;
+
+        let client: &mut tokio_postgres::Client = &mut client;
+        let transaction = client.transaction().await.map_err(internal)?;
+        // The draw stays locked until commit, so it cannot be confirmed or cancelled in between
+        draw.status = db::draw::lock_draw_status(&transaction, draw.id)
+            .await
+            .map_err(internal)?
+            .ok_or_else(|| Status::not_found(format!("Draw {} not found", draw.id)))?;
+        win_sum::validate_external_total(&draw, &win_class).map_err(Status::failed_precondition)?;
+        db::win::set_external_win_total(&transaction, draw.id, win_class.id, request.amount).await.map_err(internal)?;
+        transaction.commit().await.map_err(internal)?;
+        info!("Set external win total of class '{}' in draw {} to {}", win_class.name, draw.id, request.amount);
+
+        let reply = SetExternalWinTotalResponse {
+            success: true,
+            message: format!("External win total of class '{}' in draw {} set to {}.", win_class.name, draw.id, request.amount),
         };
         Ok(Response::new(reply))
     }
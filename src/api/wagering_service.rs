@@ -1,14 +1,23 @@
+use prost::Message;
+use tokio_postgres::Client;
 use tonic::{Request, Response, Status};
 use prost_types;
 use std::sync::Arc;
 use std::collections::HashSet;
-use tokio::sync::Mutex;
-use tokio_postgres::Client;
 use crate::db;
-use crate::core::board::{GameType};
-use crate::core::draw::Draw;
+use crate::db::Pool;
+use crate::db::wager::{IdempotencyKey, InsertWagerError};
+use crate::core::board::{Board, GameType};
+use crate::core::draw::{Draw, DrawStatus};
+use crate::core::draw_manager::DrawManager;
+use crate::core::refund;
+use crate::core::wager::{Wager, WagerStatus};
+use crate::core::win::Win;
+use crate::core::extension::Extensions;
+use crate::core::rng::Rng;
+use crate::core::{quick_pick, system_wager, validation};
 use tracing::{info, error};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 
 pub mod wagering {
     tonic::include_proto!("wagering");
@@ -19,18 +28,133 @@ use wagering::{
     PlaceWagerResponse,
     GetWagerRequest,
     GetWagerResponse,
+    CancelWagerRequest,
+    CancelWagerResponse,
 };
 
 use crate::config::app_config::Config;
 
+pub(crate) fn timestamp_to_proto(time: DateTime<Utc>) -> prost_types::Timestamp {
+    prost_types::Timestamp {
+        seconds: time.timestamp(),
+        nanos: time.timestamp_subsec_nanos() as i32,
+    }
+}
+
+pub(crate) fn uuid_to_proto(id: uuid::Uuid) -> wagering::Uuid {
+    wagering::Uuid { value: id.to_string() }
+}
+
+pub(crate) fn draw_to_proto(draw: Draw) -> wagering::Draw {
+    wagering::Draw {
+        id: draw.id,
+        game_id: Some(uuid_to_proto(draw.game_id)),
+        status: draw.status as i32,
+        created_at: Some(timestamp_to_proto(draw.created_at)),
+        modified_at: Some(timestamp_to_proto(draw.modified_at)),
+        open_time: Some(timestamp_to_proto(draw.open_time)),
+        close_time: Some(timestamp_to_proto(draw.close_time)),
+        draw_time: draw.draw_time.map(timestamp_to_proto),
+        winset_calculated_at: draw.winset_calculated_at.map(timestamp_to_proto),
+        winset_confirmed_at: draw.winset_confirmed_at.map(timestamp_to_proto),
+        winning_numbers: draw
+            .winning_numbers
+            .into_iter()
+            .map(|w| wagering::WinningNumbers {
+                draw_level_id: Some(uuid_to_proto(w.draw_level_id)),
+                numbers: w.numbers,
+            })
+            .collect(),
+    }
+}
+
+fn board_to_proto(board: Board) -> wagering::Board {
+    let game_type = match board.game_type {
+        GameType::NORMAL => wagering::GameType::Normal,
+        GameType::SYSTEM => wagering::GameType::System,
+    };
+    wagering::Board {
+        id: Some(uuid_to_proto(board.id)),
+        game_type: game_type.into(),
+        selections: board
+            .selections
+            .into_iter()
+            .map(|selection| wagering::Selection {
+                id: Some(uuid_to_proto(selection.id)),
+                name: selection.name,
+                values: selection.values,
+                machine_picked: selection.machine_picked,
+            })
+            .collect(),
+        stake: board.stake,
+    }
+}
+
+fn win_to_proto(win: Win) -> wagering::Winning {
+    wagering::Winning {
+        id: Some(uuid_to_proto(win.id)),
+        wager_id: Some(uuid_to_proto(win.wager_id)),
+        win_class_id: Some(uuid_to_proto(win.win_class_id)),
+        amount: win.amount,
+        draw_id: win.draw_id,
+        board_id: Some(uuid_to_proto(win.board_id)),
+        winning_rows: win.winning_rows,
+    }
+}
+
+fn wager_to_proto(wager: Wager, wins: Vec<Win>) -> wagering::Wager {
+    wagering::Wager {
+        id: Some(uuid_to_proto(wager.id)),
+        user_id: Some(uuid_to_proto(wager.user_id)),
+        draws: wager.draws.into_iter().map(draw_to_proto).collect(),
+        stake: wager.stake,
+        price: wager.price,
+        boards: wager.boards.into_iter().map(board_to_proto).collect(),
+        created_at: Some(timestamp_to_proto(wager.created_at)),
+        wins: wins.into_iter().map(win_to_proto).collect(),
+        status: match wager.status {
+            WagerStatus::Active => wagering::WagerStatus::Active,
+            WagerStatus::Cancelled => wagering::WagerStatus::Cancelled,
+        }
+        .into(),
+    }
+}
+
 pub struct WageringService{
-  client: Arc<Mutex<Client>>,
+  pool: Pool,
   config: Arc<Config>,
+  extensions: Arc<Extensions>,
 }
 
 impl WageringService {
-    pub fn new(client: Arc<Mutex<Client>>, config: Arc<Config>) -> Self {
-        WageringService { client, config }
+    pub fn new(pool: Pool, config: Arc<Config>, extensions: Arc<Extensions>) -> Self {
+        WageringService { pool, config, extensions }
+    }
+
+    /// Returns the wager a player already placed with an idempotency key, or `None` if the key
+    /// has not been used. A different request reusing the key is rejected.
+    async fn replayed_wager(&self, client: &Client, user_id: uuid::Uuid, idempotency_key: &IdempotencyKey) -> Result<Option<PlaceWagerResponse>, Status> {
+        let internal = |e: tokio_postgres::Error| {
+            error!("Failed to look up idempotency key '{}': {}", idempotency_key.key, e);
+            Status::internal(format!("Failed to look up idempotency key: {}", e))
+        };
+        let Some((wager_id, same_request)) = db::wager::find_idempotent_wager(client, user_id, idempotency_key).await.map_err(internal)? else {
+            return Ok(None);
+        };
+        if !same_request {
+            return Err(Status::already_exists(format!(
+                "Idempotency key '{}' was already used for a different wager request",
+                idempotency_key.key
+            )));
+        }
+        let wager = db::wager::get_wager(client, wager_id)
+            .await
+            .map_err(internal)?
+            .ok_or_else(|| Status::internal(format!("Wager {} of idempotency key '{}' not found", wager_id, idempotency_key.key)))?;
+        info!("Replaying wager {} of idempotency key '{}'", wager_id, idempotency_key.key);
+        Ok(Some(PlaceWagerResponse {
+            wager: Some(wager_to_proto(wager, Vec::new())),
+        }))
     }
 }
 
@@ -42,22 +166,60 @@ impl wagering::wagering_server::Wagering for WageringService {
     ) -> Result<Response<PlaceWagerResponse>, Status> {
         info!("Got a request: {:?}", request);
 
-        let client_locked = self.client.lock().await;
+        let mut client = self.pool.get().await.map_err(|e| {
+            error!("Failed to get a database connection: {}", e);
+            Status::unavailable(format!("Failed to get a database connection: {}", e))
+        })?;
 
         let request_data = request.into_inner();
+        let idempotency_key = (!request_data.idempotency_key.is_empty()).then(|| IdempotencyKey {
+            key: request_data.idempotency_key.clone(),
+            request: request_data.encode_to_vec(),
+        });
         let user_id = request_data.user_id.unwrap_or_default().value;
+        let user_uuid = uuid::Uuid::parse_str(&user_id).unwrap_or_default();
         let boards_proto = request_data.boards;
-        let _quick_pick = request_data.quick_pick;
+        let quick_pick = request_data.quick_pick;
+
+        if let Some(idempotency_key) = &idempotency_key
+            && let Some(reply) = self.replayed_wager(&client, user_uuid, idempotency_key).await?
+        {
+            return Ok(Response::new(reply));
+        }
 
         
 
-        // Get open draws
-        let open_draws = db::draw::get_active_draws(&client_locked, uuid::Uuid::parse_str("a1b2c3d4-e5f6-7890-1234-567890abcdef").unwrap())
+        let requested_game_id = request_data
+            .game_id
+            .as_ref()
+            .map(|game_id| uuid::Uuid::parse_str(&game_id.value))
+            .transpose()
+            .map_err(|e| Status::invalid_argument(format!("Invalid game_id UUID: {}", e)))?;
+        // Without a game id, a wager is for the game of its first draw
+        let requested_game_id = match (requested_game_id, request_data.draws.first()) {
+            (None, Some(draw_id)) if self.config.games.len() > 1 => db::draw::get_draw(&client, *draw_id)
+                .await
+                .map_err(|e| {
+                    error!("Failed to get draw {}: {}", draw_id, e);
+                    Status::internal(format!("Failed to get draw: {}", e))
+                })?
+                .map(|draw| draw.game_id),
+            (requested_game_id, _) => requested_game_id,
+        };
+        let game_config = self.config.resolve_game(requested_game_id).map_err(Status::invalid_argument)?;
+        let game_id = uuid::Uuid::parse_str(&game_config.id)
+            .map_err(|e| Status::internal(format!("Invalid game ID in config: {}", e)))?;
+
+        // Only draws still open for wagering, i.e. not past their close time
+        let open_draws: Vec<Draw> = db::draw::get_draws_by_status(&client, game_id, DrawStatus::Open)
             .await
             .map_err(|e| {
                 error!("Failed to get open draws: {}", e);
                 Status::internal(format!("Failed to get open draws: {}", e))
-            })?;
+            })?
+            .into_iter()
+            .filter(DrawManager::is_draw_open)
+            .collect();
 
         if open_draws.is_empty() {
             return Err(Status::failed_precondition("No open draws available to place wager"));
@@ -78,24 +240,37 @@ impl wagering::wagering_server::Wagering for WageringService {
             }
         }
 
-        let selected_draws: Vec<Draw> = open_draws
-            .into_iter()
-            .filter(|d| request_data.draws.contains(&d.id))
-            .collect();
+        let selected_draws = validation::validate_participation(
+            &open_draws,
+            &request_data.draws,
+            &game_config.allowed_participations,
+            game_config.allow_skipping_first_draw,
+        )
+        .map_err(Status::invalid_argument)?;
 
-        let wager_id = uuid::Uuid::new_v4();
-        let user_uuid = uuid::Uuid::parse_str(&user_id).unwrap_or_default();
+        let wager_id = uuid::Uuid::now_v7();
 
         let mut boards = Vec::new();
+        // Quick picks of all boards are drawn from one generator seeded from a secure source
+        let mut rng = if quick_pick {
+            Some(Rng::from_secure_seed().map_err(|e| {
+                error!("Failed to seed random number generator for quick pick: {}", e);
+                Status::internal("Failed to seed random number generator")
+            })?)
+        } else {
+            None
+        };
         for board_proto in &boards_proto {
-            let board_id = uuid::Uuid::new_v4();
+            // Time ordered ids keep the boards of a wager in request order when read back
+            let board_id = uuid::Uuid::now_v7();
             let mut selections = Vec::new();
             for selection_proto in &board_proto.selections {
-                let selection_id = uuid::Uuid::new_v4();
+                let selection_id = uuid::Uuid::now_v7();
                 selections.push(crate::core::selection::Selection {
                     id: selection_id,
                     name: selection_proto.name.clone(),
                     values: selection_proto.values.clone(),
+                    machine_picked: vec![false; selection_proto.values.len()],
                 });
             }
             let game_type_proto = &board_proto.game_type;
@@ -104,147 +279,90 @@ impl wagering::wagering_server::Wagering for WageringService {
                 1 => GameType::SYSTEM,
                 _ => return Err(Status::invalid_argument("Invalid game type")),
             };
-            let new_board = crate::core::board::Board {
+            let mut new_board = crate::core::board::Board {
                 id: board_id,
-                wager_id: wager_id,
+                wager_id,
                 game_type: game_type.clone(),
-                selections: selections,
+                selections,
+                stake: 0,
             };
+            if let Some(rng) = rng.as_mut() {
+                let wager_class = quick_pick::find_wager_class(&new_board, board_proto.system_size, &game_config.wager_classes)
+                    .map_err(Status::invalid_argument)?;
+                quick_pick::fill_board(&mut new_board, wager_class, &game_config.draw_levels, rng)
+                    .map_err(Status::invalid_argument)?;
+            }
             boards.push(new_board);
         }
 
         // Validate board selections against config wager_classes
-        for board in &boards {
-            // Match board.game_type to wager_class.name (e.g., "normal", "system7", etc.)
-            let game_type_str = match board.game_type {
-                GameType::NORMAL => "normal".to_string(),
-                GameType::SYSTEM => {
-                    let primary_selection = board.selections.iter().find(|s| s.name == "primary");
-                    let count = primary_selection.map(|s| s.values.len()).unwrap_or(0);
-                    format!("system{}", count)
-                },
-            };
-
-            let maybe_class = self.config.game.wager_classes.iter().find(|wc| wc.name == game_type_str);
-
-            let wager_class = match maybe_class {
-                Some(wc) => wc,
-                None => return Err(Status::invalid_argument(format!(
-                    "Invalid game type or unmatched wager class: {}",
-                    game_type_str
-                ))),
-            };
-
-            for (sel_name, required_count) in wager_class.selections.iter().zip(&wager_class.number_of_selections) {
-                let actual = board.selections.iter().find(|s| &s.name == sel_name);
-                if actual.is_none() {
-                    return Err(Status::invalid_argument(format!(
-                        "Missing selection for '{}'", sel_name
-                    )));
-                }
+        let mut stake: u32 = 0;
+        for (board, board_proto) in boards.iter_mut().zip(&boards_proto) {
+            let wager_class = system_wager::find_wager_class(board, &game_config.wager_classes)
+                .map_err(Status::invalid_argument)?;
 
-                let actual_values = &actual.unwrap().values;
-                if actual_values.len() != *required_count as usize {
-                    return Err(Status::invalid_argument(format!(
-                        "Invalid number of values for '{}': expected {}, got {}",
-                        sel_name, required_count, actual_values.len()
-                    )));
-                }
-            }
+            validation::validate_board(board, wager_class, &game_config.draw_levels)
+                .map_err(Status::invalid_argument)?;
 
-            // Extra selections not allowed
-            for s in &board.selections {
-                if !wager_class.selections.contains(&s.name) {
-                    return Err(Status::invalid_argument(format!(
-                        "Selection '{}' is not allowed for game type '{}'", s.name, game_type_str
-                    )));
-                }
-            }
+            board.stake = system_wager::board_stake(wager_class, board_proto.stake).map_err(Status::invalid_argument)?;
+            stake = stake
+                .checked_add(board.stake)
+                .ok_or_else(|| Status::invalid_argument("Wager stake is too large"))?;
         }
+        let price = system_wager::wager_price(stake, selected_draws.len()).map_err(Status::invalid_argument)?;
 
-        let new_wager = crate::core::wager::Wager {
+        let new_wager = Wager {
             id: wager_id,
             user_id: user_uuid,
             draws: selected_draws.clone(),
             boards: boards.clone(),
-            stake: 100, // TODO: Calculate actual stake based on board(s)
-            price: 100 * selected_draws.len() as u32, // TODO: Calculate actual price based on stake
+            stake,
+            price,
+            status: WagerStatus::Active,
             created_at: Utc::now(),
         };
-        db::wager::insert_wager(&client_locked, &new_wager, request_data.draws)
+        let transaction = client.transaction().await.map_err(|e| {
+            error!("Failed to start transaction: {}", e);
+            Status::internal(format!("Failed to insert wager: {}", e))
+        })?;
+        self.extensions
+            .before_wager_placed(&transaction, &new_wager)
             .await
-            .map_err(|e| {
-                error!("Failed to insert wager: {}", e);
-                Status::internal(format!("Failed to insert wager: {}", e))
-            })?;
-
-        let proto_boards: Vec<wagering::Board> = new_wager.boards.into_iter().map(|board| {
-            let proto_selections = board.selections.into_iter().map(|selection| {
-                wagering::Selection {
-                    id: Some(wagering::Uuid { value: selection.id.to_string() }),
-                    name: selection.name,
-                    values: selection.values,
+            .map_err(|e| Status::failed_precondition(format!("Wager rejected: {}", e)))?;
+        match db::wager::insert_wager(&transaction, &new_wager, request_data.draws, idempotency_key.as_ref()).await {
+            Ok(()) => {},
+            Err(InsertWagerError::DrawsNotOpen(draws)) => {
+                return Err(Status::failed_precondition(format!("Requested draws {:?} are not currently open", draws)));
+            },
+            // A concurrent request with the same key placed the wager first
+            Err(InsertWagerError::DuplicateIdempotencyKey) => {
+                drop(transaction);
+                if let Some(idempotency_key) = &idempotency_key
+                    && let Some(reply) = self.replayed_wager(&client, user_uuid, idempotency_key).await?
+                {
+                    return Ok(Response::new(reply));
                 }
-            }).collect();
-
-            let game_type = match board.game_type {
-                GameType::NORMAL => wagering::GameType::Normal,
-                GameType::SYSTEM => wagering::GameType::System,
-            };
-
-            wagering::Board {
-                id: Some(wagering::Uuid { value: board.id.to_string() }),
-                game_type: game_type.into(),
-                selections: proto_selections,
-            }
-        }).collect();
+                return Err(Status::aborted("Wager with the same idempotency key is being placed, retry the request"));
+            },
+            Err(InsertWagerError::AmountTooLarge(amount)) => {
+                return Err(Status::invalid_argument(format!("Wager amount {} is too large", amount)));
+            },
+            Err(InsertWagerError::Database(e)) => {
+                error!("Failed to insert wager: {}", e);
+                return Err(Status::internal(format!("Failed to insert wager: {}", e)));
+            },
+        }
+        self.extensions
+            .after_wager_placed(&transaction, &new_wager)
+            .await
+            .map_err(|e| Status::failed_precondition(format!("Wager rejected: {}", e)))?;
+        transaction.commit().await.map_err(|e| {
+            error!("Failed to commit wager: {}", e);
+            Status::internal(format!("Failed to insert wager: {}", e))
+        })?;
 
         let reply = PlaceWagerResponse {
-            wager: Some(wagering::Wager {
-                id: Some(wagering::Uuid { value: new_wager.id.to_string() }),
-                user_id: Some(wagering::Uuid { value: new_wager.user_id.to_string() }),
-                draws: new_wager.draws.into_iter().map(|d| wagering::Draw {
-                    id: d.id,
-                    game_id: Some(wagering::Uuid { value: d.game_id.to_string() }),
-                    status: d.status as i32,
-                    created_at: Some(prost_types::Timestamp {
-                        seconds: d.created_at.timestamp(),
-                        nanos: d.created_at.timestamp_subsec_nanos() as i32,
-                    }),
-                    modified_at: Some(prost_types::Timestamp {
-                        seconds: d.modified_at.timestamp(),
-                        nanos: d.modified_at.timestamp_subsec_nanos() as i32,
-                    }),
-                    open_time: Some(prost_types::Timestamp {
-                        seconds: d.open_time.timestamp(),
-                        nanos: d.open_time.timestamp_subsec_nanos() as i32,
-                    }),
-                    close_time: Some(prost_types::Timestamp {
-                        seconds: d.close_time.timestamp(),
-                        nanos: d.close_time.timestamp_subsec_nanos() as i32,
-                    }),
-                    draw_time: d.draw_time.map(|t| prost_types::Timestamp {
-                        seconds: t.timestamp(),
-                        nanos: t.timestamp_subsec_nanos() as i32,
-                    }),
-                    winset_calculated_at: d.winset_calculated_at.map(|t| prost_types::Timestamp {
-                        seconds: t.timestamp(),
-                        nanos: t.timestamp_subsec_nanos() as i32,
-                    }),
-                    winset_confirmed_at: d.winset_confirmed_at.map(|t| prost_types::Timestamp {
-                        seconds: t.timestamp(),
-                        nanos: t.timestamp_subsec_nanos() as i32,
-                    }),
-                    winning_numbers: vec![], // TODO: Populate winning numbers
-                }).collect(),
-                stake: new_wager.stake,
-                price: new_wager.price,
-                boards: proto_boards,
-                created_at: Some(prost_types::Timestamp {
-                    seconds: new_wager.created_at.timestamp(),
-                    nanos: new_wager.created_at.timestamp_subsec_nanos() as i32,
-                }),
-            }),
+            wager: Some(wager_to_proto(new_wager, Vec::new())),
         };
         info!("Returning PlaceWagerResponse: {:?}", reply);
         Ok(Response::new(reply))
@@ -256,10 +374,101 @@ impl wagering::wagering_server::Wagering for WageringService {
     ) -> Result<Response<GetWagerResponse>, Status> {
         info!("Got a GetWagerRequest: {:?}", request);
 
+        let wager_id_proto = request.into_inner().wager_id.unwrap_or_default();
+        let wager_id = uuid::Uuid::parse_str(&wager_id_proto.value)
+            .map_err(|e| Status::invalid_argument(format!("Invalid wager_id UUID: {}", e)))?;
+
+        let client = self.pool.get().await.map_err(|e| {
+            error!("Failed to get a database connection: {}", e);
+            Status::unavailable(format!("Failed to get a database connection: {}", e))
+        })?;
+        let wager = db::wager::get_wager(&client, wager_id)
+            .await
+            .map_err(|e| {
+                error!("Failed to get wager {}: {}", wager_id, e);
+                Status::internal(format!("Failed to get wager: {}", e))
+            })?
+            .ok_or_else(|| Status::not_found(format!("Wager {} not found", wager_id)))?;
+        let wins = db::win::get_wager_wins(&client, wager_id)
+            .await
+            .map_err(|e| {
+                error!("Failed to get wins of wager {}: {}", wager_id, e);
+                Status::internal(format!("Failed to get wins: {}", e))
+            })?;
+        drop(client);
+
         let reply = GetWagerResponse {
-            wager: None, // TODO: Implement actual wager retrieval logic
+            wager: Some(wager_to_proto(wager, wins)),
         };
         info!("Returning GetWagerResponse: {:?}", reply);
         Ok(Response::new(reply))
     }
-}
\ No newline at end of file
+
+    /// Cancels a wager on behalf of the player who placed it and refunds its price, if the
+    /// cancellation window of the game has not passed and none of its draws has closed. The
+    /// draws are locked while cancelling, so they cannot close before the cancellation commits.
+    async fn cancel_wager(
+        &self,
+        request: Request<CancelWagerRequest>,
+    ) -> Result<Response<CancelWagerResponse>, Status> {
+        info!("Got a CancelWagerRequest: {:?}", request);
+        let request_data = request.into_inner();
+        let wager_id = uuid::Uuid::parse_str(&request_data.wager_id.unwrap_or_default().value)
+            .map_err(|e| Status::invalid_argument(format!("Invalid wager_id UUID: {}", e)))?;
+        let user_id = uuid::Uuid::parse_str(&request_data.user_id.unwrap_or_default().value)
+            .map_err(|e| Status::invalid_argument(format!("Invalid user_id UUID: {}", e)))?;
+
+        let mut client = self.pool.get().await.map_err(|e| {
+            error!("Failed to get a database connection: {}", e);
+            Status::unavailable(format!("Failed to get a database connection: {}", e))
+        })?;
+        let mut wager = db::wager::get_wager(&client, wager_id)
+            .await
+            .map_err(|e| {
+                error!("Failed to get wager {}: {}", wager_id, e);
+                Status::internal(format!("Failed to get wager: {}", e))
+            })?
+            .ok_or_else(|| Status::not_found(format!("Wager {} not found", wager_id)))?;
+        if wager.user_id != user_id {
+            return Err(Status::permission_denied(format!("Wager {} was not placed by user {}", wager_id, user_id)));
+        }
+        let Some(game_config) = wager.draws.first().and_then(|draw| self.config.find_game(draw.game_id)) else {
+            return Err(Status::failed_precondition(format!("The game of wager {} is not configured", wager_id)));
+        };
+
+        let internal = |e: tokio_postgres::Error| {
+            error!("Failed to cancel wager {}: {}", wager_id, e);
+            Status::internal(format!("Failed to cancel wager: {}", e))
+        };
+        let transaction = client.transaction().await.map_err(internal)?;
+        let Some(status) = db::wager::lock_wager_status(&transaction, wager_id).await.map_err(internal)? else {
+            return Err(Status::not_found(format!("Wager {} not found", wager_id)));
+        };
+        wager.status = status;
+        wager.draws = db::draw::lock_wager_draws(&transaction, wager_id).await.map_err(internal)?;
+        validation::validate_wager_cancellation(&wager, &wager.draws, game_config.wager_cancellation_minutes, Utc::now())
+            .map_err(Status::failed_precondition)?;
+
+        let refunds = refund::wager_refunds(&wager);
+        let refunded_amount: u64 = refunds.iter().map(|r| r.amount).sum();
+        wager.status = WagerStatus::Cancelled;
+        self.extensions
+            .before_wager_cancelled(&transaction, &wager, &refunds)
+            .await
+            .map_err(|e| Status::failed_precondition(format!("Wager cancellation rejected: {}", e)))?;
+        db::wager::update_wager_status(&transaction, wager_id, &wager.status).await.map_err(internal)?;
+        db::refund::insert_refunds(&*transaction, &refunds).await.map_err(internal)?;
+        self.extensions
+            .after_wager_cancelled(&transaction, &wager, &refunds)
+            .await
+            .map_err(|e| Status::failed_precondition(format!("Wager cancellation rejected: {}", e)))?;
+        transaction.commit().await.map_err(internal)?;
+        info!("Wager {} cancelled, refunded {}", wager_id, refunded_amount);
+
+        let reply = CancelWagerResponse {
+            wager: Some(wager_to_proto(wager, Vec::new())),
+            refunded_amount,
+        };
+        Ok(Response::new(reply))
+    }
+}
@@ -1,6 +1,14 @@
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use tokio_postgres::Transaction;
 use uuid::Uuid;
+use crate::core::draw::{Draw, DrawStatus};
+use crate::core::extension::Extension;
+use crate::core::refund::Refund;
+use crate::core::wager::Wager;
+use crate::core::win_sum::WinSumSummary;
+use crate::db;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AuditLog {
@@ -11,3 +19,61 @@ pub struct AuditLog {
     pub data: serde_json::Value,
     pub created_at: DateTime<Utc>,
 }
+
+impl AuditLog {
+    /// Creates an audit log entry with a JSON snapshot of the changed entity.
+    pub fn new<T: Serialize>(entity_type: &str, entity_id: String, event_type: &str, data: &T) -> Result<Self, String> {
+        Ok(AuditLog {
+            id: Uuid::now_v7(),
+            entity_type: entity_type.to_string(),
+            entity_id,
+            event_type: event_type.to_string(),
+            data: serde_json::to_value(data).map_err(|e| e.to_string())?,
+            created_at: Utc::now(),
+        })
+    }
+}
+
+/// Extension recording every draw and wager state change in the `audit_log` table, in the
+/// same transaction as the change.
+pub struct AuditLogExtension;
+
+impl AuditLogExtension {
+    async fn record<T: Serialize + Sync>(transaction: &Transaction<'_>, entity_type: &str, entity_id: String, event_type: &str, data: &T) -> Result<(), String> {
+        let audit_log = AuditLog::new(entity_type, entity_id, event_type, data)?;
+        db::audit_log::insert_audit_log(transaction, &audit_log).await.map_err(|e| e.to_string())
+    }
+}
+
+#[async_trait]
+impl Extension for AuditLogExtension {
+    async fn after_wager_placed(&self, transaction: &Transaction<'_>, wager: &Wager) -> Result<(), String> {
+        AuditLogExtension::record(transaction, "wager", wager.id.to_string(), "WagerPlaced", wager).await
+    }
+
+    async fn after_wager_cancelled(&self, transaction: &Transaction<'_>, wager: &Wager, refunds: &[Refund]) -> Result<(), String> {
+        let mut audit_logs = vec![AuditLog::new("wager", wager.id.to_string(), "WagerCancelled", wager)?];
+        for refund in refunds {
+            audit_logs.push(AuditLog::new("wager", refund.wager_id.to_string(), "WagerRefunded", refund)?);
+        }
+        db::audit_log::insert_audit_logs(transaction, &audit_logs).await.map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    async fn after_wagers_refunded(&self, transaction: &Transaction<'_>, _draw: &Draw, refunds: &[Refund]) -> Result<(), String> {
+        let audit_logs = refunds
+            .iter()
+            .map(|refund| AuditLog::new("wager", refund.wager_id.to_string(), "WagerRefunded", refund))
+            .collect::<Result<Vec<_>, _>>()?;
+        db::audit_log::insert_audit_logs(transaction, &audit_logs).await.map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    async fn after_draw_transition(&self, transaction: &Transaction<'_>, draw: &Draw, _previous_status: &DrawStatus) -> Result<(), String> {
+        AuditLogExtension::record(transaction, "draw", draw.id.to_string(), &format!("Draw{}", draw.status), draw).await
+    }
+
+    async fn after_wins_confirmed(&self, transaction: &Transaction<'_>, draw: &Draw, win_sums: &WinSumSummary) -> Result<(), String> {
+        AuditLogExtension::record(transaction, "draw", draw.id.to_string(), "WinsConfirmed", win_sums).await
+    }
+}
@@ -1,9 +1,9 @@
 use super::selection::Selection;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use strum_macros::Display;
+use strum_macros::{Display, EnumString};
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Hash, Deserialize, Display)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Hash, Deserialize, Display, EnumString)]
 pub enum GameType {
     NORMAL,
     SYSTEM,
@@ -15,4 +15,6 @@ pub struct Board {
     pub wager_id: Uuid,
     pub game_type: GameType,
     pub selections: Vec<Selection>,
+    /// Stake of the board in one draw
+    pub stake: u32,
 }
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use crate::config::app_config::DrawLevelConfig;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct DrawLevel {
@@ -9,4 +10,22 @@ pub struct DrawLevel {
     pub number_of_selections: u32,
     pub min_value: u32,
     pub max_value: u32,
+    /// Name of the level this one shares its number space with, if any.
+    pub dependent_on: Option<String>,
+}
+
+impl DrawLevel {
+    /// Builds a draw level from configuration. The id is derived from the game id and
+    /// the level name, so it stays stable across restarts without being configured.
+    pub fn from_config(game_id: Uuid, config: &DrawLevelConfig) -> Self {
+        DrawLevel {
+            id: Uuid::new_v5(&game_id, config.name.as_bytes()),
+            game_id,
+            name: config.name.clone(),
+            number_of_selections: config.selections,
+            min_value: config.min_value,
+            max_value: config.max_value,
+            dependent_on: config.dependent_on.clone(),
+        }
+    }
 }
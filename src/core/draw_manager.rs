@@ -1,16 +1,44 @@
-use chrono::{DateTime, Utc, Duration, Timelike};
+use chrono::{DateTime, Utc, Duration};
 use crate::core::draw::{Draw, DrawStatus, WinningNumbers};
 use crate::core::draw_level::DrawLevel;
 use crate::core::rng::Rng;
 use uuid::Uuid;
-use tokio_postgres::Client;
-use crate::config::app_config::GameConfig;
-use crate::db::draw;
+use tokio_postgres::{Client, Transaction};
+use crate::config::app_config::{GameConfig, OpenPolicyConfig};
+use crate::core::extension::Extensions;
+use crate::core::refund::Refund;
+use crate::core::win_class::WinClass;
+use crate::core::{schedule, win_sum, winset};
+use crate::db::{draw, partition, refund, schedule_exception, win, win_class, Pool};
 use tokio_cron_scheduler::{JobScheduler, Job};
-use std::sync::Arc;
+use std::collections::HashSet;
+use std::sync::{Arc, LazyLock};
 use tokio::sync::Mutex;
 use tracing::{info, error};
 
+/// Number of wagers refunded per statement when a draw is cancelled.
+const REFUND_BATCH_SIZE: i64 = 10_000;
+
+/// Draws whose winset is being calculated by this process.
+static WINSET_CALCULATIONS: LazyLock<std::sync::Mutex<HashSet<i32>>> = LazyLock::new(Default::default);
+
+/// Marks the winset calculation of a draw as running until dropped.
+struct WinsetCalculationGuard(i32);
+
+impl WinsetCalculationGuard {
+    /// Returns None if a calculation is already running for the draw.
+    fn start(draw_id: i32) -> Option<Self> {
+        let mut running = WINSET_CALCULATIONS.lock().unwrap_or_else(|e| e.into_inner());
+        running.insert(draw_id).then_some(WinsetCalculationGuard(draw_id))
+    }
+}
+
+impl Drop for WinsetCalculationGuard {
+    fn drop(&mut self) {
+        WINSET_CALCULATIONS.lock().unwrap_or_else(|e| e.into_inner()).remove(&self.0);
+    }
+}
+
 pub struct DrawManager;
 
 impl DrawManager {
@@ -75,6 +103,10 @@ impl DrawManager {
                 draw.modified_at = Utc::now();
                 Ok(())
             }
+            (DrawStatus::Finalized | DrawStatus::Cancelled, DrawStatus::Cancelled) => Err(format!(
+                "A {:?} draw cannot be cancelled",
+                draw.status
+            )),
             (_, DrawStatus::Cancelled) => {
                 draw.status = new_status;
                 draw.modified_at = Utc::now();
@@ -92,20 +124,40 @@ impl DrawManager {
         draw.status == DrawStatus::Open && Utc::now() >= draw.open_time && Utc::now() < draw.close_time
     }
 
+    /// Draws the winning numbers of every draw level. Fails, leaving the draw unchanged, if a
+    /// level has fewer numbers available than it draws.
     pub fn draw_winning_numbers(
         draw: &mut Draw,
         draw_levels: &[DrawLevel],
         seed: [u8; 64],
-    ) {
+    ) -> Result<(), String> {
         let mut rng = Rng::new(seed);
         let mut winning_numbers_vec = Vec::new();
 
         for level in draw_levels {
+            // A dependent level is drawn from the same number space as its parent,
+            // so the parent's numbers cannot be drawn again.
+            let excluded: Vec<u32> = level
+                .dependent_on
+                .as_ref()
+                .and_then(|parent| draw_levels.iter().position(|l| &l.name == parent))
+                .and_then(|index| winning_numbers_vec.get(index))
+                .map(|w: &WinningNumbers| w.numbers.clone())
+                .unwrap_or_default();
+
+            let available = (level.min_value..=level.max_value).filter(|n| !excluded.contains(n)).count();
+            if available < level.number_of_selections as usize {
+                return Err(format!(
+                    "Cannot draw {} numbers for draw level '{}': only {} numbers are available",
+                    level.number_of_selections, level.name, available
+                ));
+            }
+
             let mut numbers = Vec::new();
             while numbers.len() < level.number_of_selections as usize {
                 let num = (rng.next_u64() % (level.max_value - level.min_value + 1) as u64) as u32
                     + level.min_value;
-                if !numbers.contains(&num) {
+                if !numbers.contains(&num) && !excluded.contains(&num) {
                     numbers.push(num);
                 }
             }
@@ -116,105 +168,418 @@ impl DrawManager {
             });
         }
         draw.winning_numbers = winning_numbers_vec;
+        Ok(())
     }
 
-    async fn check_and_create_draws(client: Arc<Mutex<Client>>, game_config: GameConfig) {
-        info!("Checking and creating draws for game_id: {}", game_config.id);
-        let client_locked = client.lock().await;
+    /// Validates externally drawn winning numbers: every draw level gets exactly its number of
+    /// distinct numbers within its range, and a dependent level does not repeat numbers of its
+    /// parent level.
+    pub fn validate_winning_numbers(draw_levels: &[DrawLevel], winning_numbers: &[WinningNumbers]) -> Result<(), String> {
+        if let Some(unknown) = winning_numbers.iter().find(|w| !draw_levels.iter().any(|l| l.id == w.draw_level_id)) {
+            return Err(format!("Unknown draw level {}", unknown.draw_level_id));
+        }
+        for level in draw_levels {
+            let mut matching = winning_numbers.iter().filter(|w| w.draw_level_id == level.id);
+            let (Some(numbers), None) = (matching.next(), matching.next()) else {
+                return Err(format!("Expected one set of winning numbers for draw level '{}'", level.name));
+            };
+            if numbers.numbers.len() != level.number_of_selections as usize {
+                return Err(format!(
+                    "Expected {} winning numbers for draw level '{}', got {}",
+                    level.number_of_selections, level.name, numbers.numbers.len()
+                ));
+            }
+            if let Some(number) = numbers.numbers.iter().find(|n| **n < level.min_value || **n > level.max_value) {
+                return Err(format!(
+                    "Winning number {} for draw level '{}' is not between {} and {}",
+                    number, level.name, level.min_value, level.max_value
+                ));
+            }
+            let mut sorted = numbers.numbers.clone();
+            sorted.sort_unstable();
+            if let Some(pair) = sorted.windows(2).find(|pair| pair[0] == pair[1]) {
+                return Err(format!("Winning number {} is repeated for draw level '{}'", pair[0], level.name));
+            }
+            let parent_numbers = level
+                .dependent_on
+                .as_ref()
+                .and_then(|parent| draw_levels.iter().find(|l| &l.name == parent))
+                .and_then(|parent| winning_numbers.iter().find(|w| w.draw_level_id == parent.id));
+            if let Some(parent_numbers) = parent_numbers
+                && let Some(number) = numbers.numbers.iter().find(|n| parent_numbers.numbers.contains(n))
+            {
+                return Err(format!("Winning number {} for draw level '{}' repeats a number of its parent level", number, level.name));
+            }
+        }
+        Ok(())
+    }
 
-        let game_id = uuid::Uuid::parse_str(&game_config.id).expect("Invalid game ID in config");
-        let open_draws_config = game_config.open_draws;
-        let closed_state_duration = Duration::seconds(game_config.closed_state_duration_seconds as i64);
+    /// Applies a status transition to the draw and persists it, calling the transition hooks
+    /// of the extensions before and after the update.
+    async fn transition_in_transaction(transaction: &Transaction<'_>, extensions: &Extensions, draw: &mut Draw, new_status: DrawStatus) -> Result<(), String> {
+        // The draw row stays locked until commit, so concurrent transitions of the same draw cannot both succeed
+        let current_status = draw::lock_draw_status(transaction, draw.id).await.map_err(|e| e.to_string())?;
+        if current_status.as_ref() != Some(&draw.status) {
+            return Err(format!("Draw {} is no longer in status {:?}", draw.id, draw.status));
+        }
+        if new_status == DrawStatus::Open && !partition::has_draw_partitions(transaction, draw.id).await.map_err(|e| e.to_string())? {
+            return Err(format!("Draw {} cannot open without its partitions of the wager tables", draw.id));
+        }
+        extensions.before_draw_transition(transaction, draw, &new_status).await?;
+        let previous_status = draw.status.clone();
+        DrawManager::transition_draw_status(draw, new_status)?;
+        draw::update_draw(transaction, draw).await.map_err(|e| e.to_string())?;
+        extensions.after_draw_transition(transaction, draw, &previous_status).await
+    }
 
-        // Transition created draws to open
-        match draw::get_created_draws_ready_to_open(&client_locked, game_id).await {
-            Ok(mut created_draws) => {
-                info!("Found {} created draws ready to open for game_id: {}", created_draws.len(), game_id);
-                for draw in created_draws.iter_mut() {
-                    if let Ok(_) = DrawManager::transition_draw_status(draw, DrawStatus::Open) {
-                        match draw::update_draw_status(&client_locked, draw.id, DrawStatus::Open).await {
-                            Ok(_) => info!("Successfully transitioned draw {} to Open", draw.id),
-                            Err(e) => error!("Failed to update draw {} status to Open: {}", draw.id, e),
-                        }
-                    }
+    /// Applies a status transition to the draw and persists the new status and timestamps in
+    /// a transaction. The draw is left unchanged if the transition fails or is vetoed.
+    pub async fn persist_transition(client: &mut Client, extensions: &Extensions, draw: &mut Draw, new_status: DrawStatus) -> Result<(), String> {
+        let transaction = client.transaction().await.map_err(|e| e.to_string())?;
+        let mut updated = draw.clone();
+        DrawManager::transition_in_transaction(&transaction, extensions, &mut updated, new_status).await?;
+        transaction.commit().await.map_err(|e| e.to_string())?;
+        *draw = updated;
+        Ok(())
+    }
+
+    /// Opens a created draw for wagers, creating its partitions of the wager tables first if
+    /// they are missing. The draw stays Created if they cannot be created.
+    pub async fn open_draw(client: &mut Client, extensions: &Extensions, draw: &mut Draw) -> Result<(), String> {
+        partition::create_draw_partitions(client, draw.id)
+            .await
+            .map_err(|e| format!("Failed to create partitions: {}", e))?;
+        DrawManager::persist_transition(client, extensions, draw, DrawStatus::Open).await
+    }
+
+    /// Cancels a draw and refunds the stake of every participating wager for it. The draw is
+    /// moved to Cancelled and its calculated wins are removed in one transaction, then the
+    /// wagers are refunded by `refund_draw`. Returns the number of refunded wagers.
+    pub async fn cancel_draw(client: &mut Client, extensions: &Extensions, draw: &mut Draw) -> Result<u64, String> {
+        let transaction = client.transaction().await.map_err(|e| e.to_string())?;
+        let mut updated = draw.clone();
+        DrawManager::transition_in_transaction(&transaction, extensions, &mut updated, DrawStatus::Cancelled).await?;
+        win::delete_wins_for_draw(&transaction, draw.id).await.map_err(|e| e.to_string())?;
+        transaction.commit().await.map_err(|e| e.to_string())?;
+        *draw = updated;
+
+        DrawManager::refund_draw(client, extensions, draw).await
+    }
+
+    /// Refunds the stake of every active wager participating in a cancelled draw that has not
+    /// been refunded for it yet, in one transaction per batch of wagers. Completes the refunds
+    /// of an interrupted cancellation when called again. Returns the number of refunded wagers.
+    pub async fn refund_draw(client: &mut Client, extensions: &Extensions, draw: &Draw) -> Result<u64, String> {
+        let mut refunded = 0;
+        let mut after_wager_id = Uuid::nil();
+        loop {
+            let transaction = client.transaction().await.map_err(|e| e.to_string())?;
+            // Concurrent refunds of the draw wait for each other, so no wager is refunded twice
+            let status = draw::lock_draw_status(&transaction, draw.id).await.map_err(|e| e.to_string())?;
+            if status != Some(DrawStatus::Cancelled) {
+                return Err(format!("Draw {} is not cancelled", draw.id));
+            }
+            let stakes = refund::get_draw_wager_stakes_batch(&transaction, draw.id, after_wager_id, REFUND_BATCH_SIZE)
+                .await
+                .map_err(|e| e.to_string())?;
+            let Some((last_wager_id, _)) = stakes.last() else {
+                break;
+            };
+            after_wager_id = *last_wager_id;
+            let refunds: Vec<Refund> = stakes.iter().map(|(wager_id, stake)| Refund::new(*wager_id, draw.id, *stake)).collect();
+            extensions.before_wagers_refunded(&transaction, draw, &refunds).await?;
+            let inserted = refund::insert_refunds(&transaction, &refunds).await.map_err(|e| e.to_string())?;
+            extensions.after_wagers_refunded(&transaction, draw, &refunds).await?;
+            transaction.commit().await.map_err(|e| e.to_string())?;
+            refunded += inserted;
+        }
+        Ok(refunded)
+    }
+
+    /// Calculates the win sums of a draw with a calculated winset and moves it to
+    /// WinsetConfirmed, calling the wins confirmed hooks of the extensions, all in one
+    /// transaction with the draw locked. Returns false, changing nothing, while the draw is
+    /// waiting for external win class totals.
+    async fn confirm_wins(client: &mut Client, extensions: &Extensions, draw: &mut Draw, win_classes: &[WinClass]) -> Result<bool, String> {
+        let transaction = client.transaction().await.map_err(|e| e.to_string())?;
+        // External totals are set with the draw locked, so they cannot change until commit
+        let status = draw::lock_draw_status(&transaction, draw.id).await.map_err(|e| e.to_string())?;
+        if status.as_ref() != Some(&draw.status) {
+            return Err(format!("Draw {} is no longer in status {:?}", draw.id, draw.status));
+        }
+        let Some(win_sums) = win_sum::calculate_win_sums(&transaction, draw, win_classes).await.map_err(|e| e.to_string())? else {
+            return Ok(false);
+        };
+        let mut updated = draw.clone();
+        extensions.before_wins_confirmed(&transaction, draw, &win_sums).await?;
+        DrawManager::transition_in_transaction(&transaction, extensions, &mut updated, DrawStatus::WinsetConfirmed).await?;
+        extensions.after_wins_confirmed(&transaction, &updated, &win_sums).await?;
+        transaction.commit().await.map_err(|e| e.to_string())?;
+        *draw = updated;
+        Ok(true)
+    }
+
+    async fn transition_draws(client: &mut Client, extensions: &Extensions, draws: Vec<Draw>, new_status: DrawStatus) {
+        for mut draw in draws {
+            match DrawManager::persist_transition(client, extensions, &mut draw, new_status.clone()).await {
+                Ok(_) => info!("Successfully transitioned draw {} to {:?}", draw.id, new_status),
+                Err(e) => error!("Failed to transition draw {} to {:?}: {}", draw.id, new_status, e),
+            }
+        }
+    }
+
+    /// Starts calculating the winset of a drawn draw in the background and moves it to
+    /// WinsetCalculated when done. Does nothing and returns false if a calculation is already
+    /// running for the draw.
+    pub fn spawn_winset_calculation(pool: Pool, extensions: Arc<Extensions>, drawn_draw: Draw, draw_levels: Vec<DrawLevel>) -> bool {
+        let Some(guard) = WinsetCalculationGuard::start(drawn_draw.id) else {
+            return false;
+        };
+        tokio::spawn(async move {
+            DrawManager::run_winset_calculation(pool, extensions, drawn_draw, draw_levels).await;
+            drop(guard);
+        });
+        true
+    }
+
+    async fn run_winset_calculation(pool: Pool, extensions: Arc<Extensions>, mut drawn_draw: Draw, draw_levels: Vec<DrawLevel>) {
+        let mut client = match pool.get().await {
+            Ok(client) => client,
+            Err(e) => {
+                error!("Failed to get a database connection for the winset calculation of draw {}: {}", drawn_draw.id, e);
+                return;
+            }
+        };
+        match winset::calculate_winset(&mut client, &drawn_draw, &draw_levels).await {
+            Ok(_) => {
+                match DrawManager::persist_transition(&mut client, &extensions, &mut drawn_draw, DrawStatus::WinsetCalculated).await {
+                    Ok(_) => info!("Successfully transitioned draw {} to WinsetCalculated", drawn_draw.id),
+                    Err(e) => error!("Failed to transition draw {} to WinsetCalculated: {}", drawn_draw.id, e),
                 }
             },
+            Err(e) => error!("Failed to calculate winset for draw {}: {}", drawn_draw.id, e),
+        }
+    }
+
+    /// Drives existing draws through the rest of the state machine: closes draws at
+    /// close time, draws numbers at draw time for internally drawn games, calculates the
+    /// winset of drawn draws in the background, and moves draws with a calculated winset
+    /// through win sum calculation and confirmation to Finalized.
+    async fn advance_draws(pool: Pool, extensions: Arc<Extensions>, game_config: GameConfig) {
+        info!("Advancing draws for game_id: {}", game_config.id);
+        let mut client = match pool.get().await {
+            Ok(client) => client,
             Err(e) => {
-                error!("Failed to get created draws ready to open: {}", e);
+                error!("Failed to get a database connection: {}", e);
+                return;
             }
-        }
+        };
 
-        match draw::get_active_draws(&*client_locked, game_id).await {
-            Ok(mut active_draws) => {
-                info!("Found {} active draws for game_id: {}", active_draws.len(), game_id);
+        let game_id = uuid::Uuid::parse_str(&game_config.id).expect("Invalid game ID in config");
+        let draw_levels: Vec<DrawLevel> = game_config
+            .draw_levels
+            .iter()
+            .map(|level| DrawLevel::from_config(game_id, level))
+            .collect();
 
-                while active_draws.len() < open_draws_config as usize {
-                    let now = Utc::now();
-                    let mut last_scheduled_draw_time = now;
+        match draw::get_open_draws_ready_to_close(&client, game_id).await {
+            Ok(draws) => DrawManager::transition_draws(&mut client, &extensions, draws, DrawStatus::Closed).await,
+            Err(e) => error!("Failed to get open draws ready to close: {}", e),
+        }
 
-                    if let Some(latest_draw) = active_draws.iter().max_by_key(|d| d.draw_time) {
-                        if let Some(dt) = latest_draw.draw_time {
-                            last_scheduled_draw_time = dt;
+        // Externally drawn games stay Closed until the numbers arrive through the Admin API
+        if !game_config.externally_drawn {
+            match draw::get_closed_draws_ready_to_draw(&client, game_id).await {
+                Ok(draws) => {
+                    for mut closed_draw in draws {
+                        let mut seed = [0u8; 64];
+                        if let Err(e) = getrandom::fill(&mut seed) {
+                            error!("Failed to seed random number generator for draw {}: {}", closed_draw.id, e);
+                            continue;
+                        }
+                        if let Err(e) = DrawManager::draw_winning_numbers(&mut closed_draw, &draw_levels, seed) {
+                            error!("Failed to draw winning numbers for draw {}: {}", closed_draw.id, e);
+                            continue;
+                        }
+                        info!("Drew winning numbers for draw {}: {:?}", closed_draw.id, closed_draw.winning_numbers);
+                        match DrawManager::persist_transition(&mut client, &extensions, &mut closed_draw, DrawStatus::Drawn).await {
+                            Ok(_) => info!("Successfully transitioned draw {} to Drawn", closed_draw.id),
+                            Err(e) => error!("Failed to transition draw {} to Drawn: {}", closed_draw.id, e),
                         }
                     }
+                },
+                Err(e) => error!("Failed to get closed draws ready to draw: {}", e),
+            }
+        }
 
-                    let mut next_scheduled_draw_time = last_scheduled_draw_time;
-
-                    // Calculate next_scheduled_draw_time based on schedule
-                    match &game_config.schedule {
-                        crate::config::app_config::ScheduleConfig::Daily { time } => {
-                            let parts: Vec<&str> = time.split(':').collect();
-                            let hour = parts[0].parse::<u32>().unwrap();
-                            let minute = parts[1].parse::<u32>().unwrap();
-
-                            next_scheduled_draw_time = next_scheduled_draw_time.with_hour(hour).unwrap().with_minute(minute).unwrap().with_second(0).unwrap().with_nanosecond(0).unwrap();
-                            if next_scheduled_draw_time <= last_scheduled_draw_time {
-                                next_scheduled_draw_time = next_scheduled_draw_time + Duration::days(1);
-                            }
-                        },
-                        _ => unimplemented!(), // TODO: Implement other schedule types
+        // Calculations that failed or were interrupted, e.g. by a restart, are retried on every tick
+        match draw::get_draws_by_status(&client, game_id, DrawStatus::Drawn).await {
+            Ok(draws) => {
+                for drawn_draw in draws {
+                    let draw_id = drawn_draw.id;
+                    if DrawManager::spawn_winset_calculation(pool.clone(), extensions.clone(), drawn_draw, draw_levels.clone()) {
+                        info!("Started winset calculation for draw {}", draw_id);
                     }
+                }
+            },
+            Err(e) => error!("Failed to get drawn draws: {}", e),
+        }
 
-                    let draw_time = next_scheduled_draw_time;
-                    let close_time = draw_time - closed_state_duration;
-                    let open_time = Utc::now(); // Current timestamp upon initial creation
-
-                    let new_draw = DrawManager::new_draw(game_id, open_time, close_time, draw_time);
-                    match draw::insert_draw(&*client_locked, &new_draw).await {
-                        Ok(_) => {
-                            info!("Successfully inserted new draw: {:?}", new_draw);
-                            active_draws.push(new_draw);
-                            info!("Created new draw. Total active draws: {}", active_draws.len());
-                        },
-                        Err(e) => {
-                            error!("Failed to insert draw: {}", e);
+        // The winset is confirmed once win sums are known; external win classes with winners
+        // keep the draw waiting until their totals have been set
+        match draw::get_draws_by_status(&client, game_id, DrawStatus::WinsetCalculated).await {
+            Ok(draws) if draws.is_empty() => {},
+            Ok(draws) => match win_class::get_win_classes(&client, game_id).await {
+                Ok(win_classes) => {
+                    for mut calculated_draw in draws {
+                        match DrawManager::confirm_wins(&mut client, &extensions, &mut calculated_draw, &win_classes).await {
+                            Ok(true) => info!("Successfully transitioned draw {} to WinsetConfirmed", calculated_draw.id),
+                            Ok(false) => info!("Draw {} is waiting for external win class totals", calculated_draw.id),
+                            Err(e) => error!("Failed to confirm the wins of draw {}: {}", calculated_draw.id, e),
                         }
                     }
+                },
+                Err(e) => error!("Failed to get win classes: {}", e),
+            },
+            Err(e) => error!("Failed to get draws with calculated winset: {}", e),
+        }
+
+        match draw::get_draws_by_status(&client, game_id, DrawStatus::WinsetConfirmed).await {
+            Ok(draws) => DrawManager::transition_draws(&mut client, &extensions, draws, DrawStatus::Finalized).await,
+            Err(e) => error!("Failed to get draws with confirmed winset: {}", e),
+        }
+    }
+
+    /// Open time of a new draw under the open policy of the game. `previous_close_time` is the
+    /// close time of the draw scheduled before it. Open times are never earlier than `now`.
+    pub fn policy_open_time(
+        open_policy: &OpenPolicyConfig,
+        now: DateTime<Utc>,
+        draw_time: DateTime<Utc>,
+        previous_close_time: DateTime<Utc>,
+    ) -> DateTime<Utc> {
+        let open_time = match open_policy {
+            OpenPolicyConfig::Immediately => now,
+            OpenPolicyConfig::BeforeDraw { hours } => draw_time - Duration::hours(*hours as i64),
+            OpenPolicyConfig::AfterPreviousClose => previous_close_time,
+        };
+        open_time.max(now)
+    }
+
+    /// Creates draws until the game has `open_draws` created or open draws, following the
+    /// schedule and its exceptions from the configuration and the database. Planned draws that
+    /// already exist in any status, or that would already be closed, are not created.
+    /// Returns the number of created draws.
+    pub async fn plan_draws(client: &mut Client, game_config: &GameConfig) -> Result<u64, String> {
+        let game_id = uuid::Uuid::parse_str(&game_config.id).map_err(|e| format!("Invalid game ID in config: {}", e))?;
+        let closed_state_duration = Duration::seconds(game_config.closed_state_duration_seconds as i64);
+
+        let transaction = client.transaction().await.map_err(|e| e.to_string())?;
+        schedule_exception::lock_game_schedule(&transaction, game_id).await.map_err(|e| e.to_string())?;
+        let mut exceptions = game_config.schedule_exceptions.clone();
+        exceptions.extend(schedule_exception::get_schedule_exceptions(&transaction, game_id).await.map_err(|e| e.to_string())?);
+
+        let now = Utc::now();
+        let mut active_draws = draw::get_active_draws(&transaction, game_id).await.map_err(|e| e.to_string())?.len();
+        let existing_draw_times = draw::get_draw_times_after(&transaction, game_id, now).await.map_err(|e| e.to_string())?;
+        info!("Found {} active draws for game_id: {}", active_draws, game_id);
+
+        let mut created = Vec::new();
+        let mut after = now;
+        let mut previous_close_time = now;
+        while active_draws < game_config.open_draws as usize {
+            let planned = schedule::next_planned_draw(&game_config.schedule, game_config.timezone, &exceptions, after)?;
+            after = planned.draw_time;
+            let close_time = planned.close_time.unwrap_or(planned.draw_time - closed_state_duration);
+            let previous_close = std::mem::replace(&mut previous_close_time, close_time);
+            if existing_draw_times.contains(&planned.draw_time) || close_time <= now {
+                continue;
+            }
+            // Extra draws may set their own open time
+            let open_time = planned
+                .open_time
+                .unwrap_or_else(|| DrawManager::policy_open_time(&game_config.open_policy, now, planned.draw_time, previous_close));
+
+            let new_draw = DrawManager::new_draw(game_id, open_time, close_time, planned.draw_time);
+            created.push(draw::insert_draw(&transaction, &new_draw).await.map_err(|e| e.to_string())?);
+            active_draws += 1;
+        }
+        transaction.commit().await.map_err(|e| e.to_string())?;
+
+        // Draws whose partitions fail here stay Created until the partitions are created when opening
+        let mut failed = Vec::new();
+        for draw_id in &created {
+            if let Err(e) = partition::create_draw_partitions(client, *draw_id).await {
+                failed.push(format!("draw {}: {}", draw_id, e));
+            }
+        }
+        if !failed.is_empty() {
+            return Err(format!("Created {} draws, but failed to create partitions for {}", created.len(), failed.join(", ")));
+        }
+        Ok(created.len() as u64)
+    }
+
+    async fn check_and_create_draws(pool: Pool, extensions: Arc<Extensions>, game_config: GameConfig) {
+        info!("Checking and creating draws for game_id: {}", game_config.id);
+        let mut client = match pool.get().await {
+            Ok(client) => client,
+            Err(e) => {
+                error!("Failed to get a database connection: {}", e);
+                return;
+            }
+        };
+
+        let game_id = uuid::Uuid::parse_str(&game_config.id).expect("Invalid game ID in config");
+
+        // Transition created draws to open
+        match draw::get_created_draws_ready_to_open(&client, game_id).await {
+            Ok(created_draws) => {
+                info!("Found {} created draws ready to open for game_id: {}", created_draws.len(), game_id);
+                for mut created_draw in created_draws {
+                    match DrawManager::open_draw(&mut client, &extensions, &mut created_draw).await {
+                        Ok(_) => info!("Successfully transitioned draw {} to Open", created_draw.id),
+                        Err(e) => error!("Failed to transition draw {} to Open: {}", created_draw.id, e),
+                    }
                 }
             },
             Err(e) => {
-                error!("Failed to get active draws: {}", e);
+                error!("Failed to get created draws ready to open: {}", e);
             }
         }
+
+        match DrawManager::plan_draws(&mut client, &game_config).await {
+            Ok(created) => info!("Created {} new draws for game_id: {}", created, game_id),
+            Err(e) => error!("Failed to create draws for game_id {}: {}", game_id, e),
+        }
     }
 
-    pub async fn schedule_draws(client: Arc<Mutex<Client>>, game_config: GameConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    pub async fn schedule_draws(pool: Pool, extensions: Arc<Extensions>, game_config: GameConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let _game_id = uuid::Uuid::parse_str(&game_config.id).expect("Invalid game ID in config");
         let _open_draws_config = game_config.open_draws;
 
         // Run once on startup
-        DrawManager::check_and_create_draws(client.clone(), game_config.clone()).await;
+        DrawManager::advance_draws(pool.clone(), extensions.clone(), game_config.clone()).await;
+        DrawManager::check_and_create_draws(pool.clone(), extensions.clone(), game_config.clone()).await;
 
         let sched = JobScheduler::new().await?;
-        let client_clone = client.clone();
+        let pool_clone = pool.clone();
         let game_config_clone = game_config.clone();
+        // Ticks never overlap: a tick still running when the next one fires causes that one to be skipped
+        let running = Arc::new(Mutex::new(()));
 
         let job = Job::new_async("1/10 * * * * *", move |_uuid, _l| {
-            let client_clone = client_clone.clone();
+            let pool_clone = pool_clone.clone();
+            let extensions = extensions.clone();
             let game_config_clone = game_config_clone.clone();
+            let running = running.clone();
             Box::pin(async move {
-                info!("Cron job triggered: Checking and creating draws.");
-                DrawManager::check_and_create_draws(client_clone, game_config_clone).await;
+                let Ok(_running) = running.try_lock() else {
+                    info!("Previous draw management tick still running, skipping this one.");
+                    return;
+                };
+                info!("Cron job triggered: Advancing, checking and creating draws.");
+                DrawManager::advance_draws(pool_clone.clone(), extensions.clone(), game_config_clone.clone()).await;
+                DrawManager::check_and_create_draws(pool_clone, extensions, game_config_clone).await;
             })
         })?;
         sched.add(job).await.expect("Failed to add job to scheduler");
@@ -0,0 +1,147 @@
+//! Extension points on draw and wager state changes.
+//!
+//! Extensions are registered at startup and called inside the database transaction that
+//! persists a change, before and after the change is written. An extension can write to the
+//! database through the same transaction, e.g. to debit a wallet or book a ledger entry, and
+//! vetoes the change by returning an error, which rolls the whole transaction back.
+
+use std::sync::Arc;
+use async_trait::async_trait;
+use tokio_postgres::Transaction;
+use crate::core::draw::{Draw, DrawStatus};
+use crate::core::refund::Refund;
+use crate::core::wager::Wager;
+use crate::core::win_sum::WinSumSummary;
+
+/// Hooks on draw and wager state changes. All hooks default to accepting the change.
+#[async_trait]
+pub trait Extension: Send + Sync {
+    async fn before_wager_placed(&self, _transaction: &Transaction<'_>, _wager: &Wager) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn after_wager_placed(&self, _transaction: &Transaction<'_>, _wager: &Wager) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Called with the refunds of the wager for each of its draws, before they are recorded.
+    async fn before_wager_cancelled(&self, _transaction: &Transaction<'_>, _wager: &Wager, _refunds: &[Refund]) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn after_wager_cancelled(&self, _transaction: &Transaction<'_>, _wager: &Wager, _refunds: &[Refund]) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Called with a batch of refunds for a cancelled draw, before they are recorded. A draw
+    /// with many wagers is refunded in several batches, each in its own transaction.
+    async fn before_wagers_refunded(&self, _transaction: &Transaction<'_>, _draw: &Draw, _refunds: &[Refund]) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn after_wagers_refunded(&self, _transaction: &Transaction<'_>, _draw: &Draw, _refunds: &[Refund]) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Called with the draw still in its current status.
+    async fn before_draw_transition(&self, _transaction: &Transaction<'_>, _draw: &Draw, _new_status: &DrawStatus) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Called with the draw already in its new status.
+    async fn after_draw_transition(&self, _transaction: &Transaction<'_>, _draw: &Draw, _previous_status: &DrawStatus) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Called when the win sums of a draw are confirmed, in the transaction moving the draw to
+    /// WinsetConfirmed and before the draw transition hooks.
+    async fn before_wins_confirmed(&self, _transaction: &Transaction<'_>, _draw: &Draw, _win_sums: &WinSumSummary) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn after_wins_confirmed(&self, _transaction: &Transaction<'_>, _draw: &Draw, _win_sums: &WinSumSummary) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// The registered extensions, called in registration order. The first veto stops the call chain.
+#[derive(Clone, Default)]
+pub struct Extensions {
+    extensions: Vec<Arc<dyn Extension>>,
+}
+
+impl Extensions {
+    pub fn register(&mut self, extension: Arc<dyn Extension>) {
+        self.extensions.push(extension);
+    }
+
+    pub async fn before_wager_placed(&self, transaction: &Transaction<'_>, wager: &Wager) -> Result<(), String> {
+        for extension in &self.extensions {
+            extension.before_wager_placed(transaction, wager).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn after_wager_placed(&self, transaction: &Transaction<'_>, wager: &Wager) -> Result<(), String> {
+        for extension in &self.extensions {
+            extension.after_wager_placed(transaction, wager).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn before_wager_cancelled(&self, transaction: &Transaction<'_>, wager: &Wager, refunds: &[Refund]) -> Result<(), String> {
+        for extension in &self.extensions {
+            extension.before_wager_cancelled(transaction, wager, refunds).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn after_wager_cancelled(&self, transaction: &Transaction<'_>, wager: &Wager, refunds: &[Refund]) -> Result<(), String> {
+        for extension in &self.extensions {
+            extension.after_wager_cancelled(transaction, wager, refunds).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn before_wagers_refunded(&self, transaction: &Transaction<'_>, draw: &Draw, refunds: &[Refund]) -> Result<(), String> {
+        for extension in &self.extensions {
+            extension.before_wagers_refunded(transaction, draw, refunds).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn after_wagers_refunded(&self, transaction: &Transaction<'_>, draw: &Draw, refunds: &[Refund]) -> Result<(), String> {
+        for extension in &self.extensions {
+            extension.after_wagers_refunded(transaction, draw, refunds).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn before_draw_transition(&self, transaction: &Transaction<'_>, draw: &Draw, new_status: &DrawStatus) -> Result<(), String> {
+        for extension in &self.extensions {
+            extension.before_draw_transition(transaction, draw, new_status).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn after_draw_transition(&self, transaction: &Transaction<'_>, draw: &Draw, previous_status: &DrawStatus) -> Result<(), String> {
+        for extension in &self.extensions {
+            extension.after_draw_transition(transaction, draw, previous_status).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn before_wins_confirmed(&self, transaction: &Transaction<'_>, draw: &Draw, win_sums: &WinSumSummary) -> Result<(), String> {
+        for extension in &self.extensions {
+            extension.before_wins_confirmed(transaction, draw, win_sums).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn after_wins_confirmed(&self, transaction: &Transaction<'_>, draw: &Draw, win_sums: &WinSumSummary) -> Result<(), String> {
+        for extension in &self.extensions {
+            extension.after_wins_confirmed(transaction, draw, win_sums).await?;
+        }
+        Ok(())
+    }
+}
@@ -10,3 +10,12 @@ pub mod win;
 pub mod audit_log;
 pub mod rng;
 pub mod draw_manager;
+pub mod system_wager;
+pub mod quick_pick;
+pub mod extension;
+pub mod validation;
+pub mod winset;
+pub mod win_sum;
+pub mod schedule;
+pub mod refund;
+pub mod retention;
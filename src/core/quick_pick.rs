@@ -0,0 +1,86 @@
+//! Quick pick: fills the selections of a board with machine-picked numbers up to the number
+//! of values its wager class requires. Numbers the player picked are kept.
+
+use uuid::Uuid;
+use crate::config::app_config::{DrawLevelConfig, WagerClassConfig};
+use crate::core::board::{Board, GameType};
+use crate::core::rng::Rng;
+use crate::core::selection::Selection;
+use crate::core::system_wager;
+
+/// Wager class a board is quick picked for. A system board states its size explicitly with
+/// `system_size`, as its primary numbers may still be missing; 0 derives the class from the
+/// numbers already selected.
+pub fn find_wager_class<'a>(board: &Board, system_size: u32, wager_classes: &'a [WagerClassConfig]) -> Result<&'a WagerClassConfig, String> {
+    if board.game_type == GameType::SYSTEM && system_size > 0 {
+        let name = format!("system{}", system_size);
+        return wager_classes
+            .iter()
+            .find(|wc| wc.name == name)
+            .ok_or_else(|| format!("Invalid game type or unmatched wager class: {}", name));
+    }
+    system_wager::find_wager_class(board, wager_classes)
+}
+
+/// Fills missing and partially filled selections of a board. Numbers are drawn between the
+/// `min_value` and `max_value` of the selection's draw level, and a dependent level never gets
+/// a number already selected for its parent level. Values end up sorted with their
+/// machine-picked flags.
+pub fn fill_board(board: &mut Board, wager_class: &WagerClassConfig, draw_levels: &[DrawLevelConfig], rng: &mut Rng) -> Result<(), String> {
+    // Parents are configured before their dependent levels, so they are filled first
+    for level in draw_levels {
+        let Some(position) = wager_class.selections.iter().position(|s| s == &level.name) else {
+            continue;
+        };
+        let required = wager_class.number_of_selections.get(position).copied().unwrap_or(level.selections) as usize;
+
+        let excluded: Vec<i32> = level
+            .dependent_on
+            .as_ref()
+            .and_then(|parent| board.selections.iter().find(|s| &s.name == parent))
+            .map(|s| s.values.clone())
+            .unwrap_or_default();
+
+        let index = match board.selections.iter().position(|s| s.name == level.name) {
+            Some(index) => index,
+            None => {
+                board.selections.push(Selection {
+                    id: Uuid::now_v7(),
+                    name: level.name.clone(),
+                    values: Vec::new(),
+                    machine_picked: Vec::new(),
+                });
+                board.selections.len() - 1
+            }
+        };
+        let selection = &mut board.selections[index];
+        selection.machine_picked.resize(selection.values.len(), false);
+        if selection.values.len() >= required {
+            continue;
+        }
+
+        let min_value = level.min_value as i32;
+        let max_value = level.max_value as i32;
+        let available = (min_value..=max_value)
+            .filter(|n| !selection.values.contains(n) && !excluded.contains(n))
+            .count();
+        if available < required - selection.values.len() {
+            return Err(format!(
+                "Cannot quick pick {} numbers for '{}': only {} numbers are available",
+                required - selection.values.len(),
+                level.name,
+                available
+            ));
+        }
+
+        while selection.values.len() < required {
+            let num = (rng.next_u64() % (max_value - min_value + 1) as u64) as i32 + min_value;
+            if !selection.values.contains(&num) && !excluded.contains(&num) {
+                selection.values.push(num);
+                selection.machine_picked.push(true);
+            }
+        }
+        selection.canonicalise();
+    }
+    Ok(())
+}
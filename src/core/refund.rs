@@ -0,0 +1,37 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use crate::core::wager::Wager;
+
+/// Stake refunded to a wager for one cancelled draw. A wager participating in several draws
+/// gets back the share of its price paid for that draw, i.e. its stake.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Refund {
+    pub id: Uuid,
+    pub wager_id: Uuid,
+    pub draw_id: i32,
+    pub amount: u64,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Refund {
+    pub fn new(wager_id: Uuid, draw_id: i32, amount: u64) -> Self {
+        Refund {
+            id: Uuid::now_v7(),
+            wager_id,
+            draw_id,
+            amount,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Refunds of a cancelled wager, one per draw it participates in. Together they return the
+/// price of the wager.
+pub fn wager_refunds(wager: &Wager) -> Vec<Refund> {
+    wager
+        .draws
+        .iter()
+        .map(|draw| Refund::new(wager.id, draw.id, wager.stake as u64))
+        .collect()
+}
@@ -0,0 +1,112 @@
+//! Scheduled deletion of finalized and cancelled draws older than the configured age, with the
+//! wagers whose last draw they are. The wager tables are partitioned per draw, so their rows are
+//! removed by detaching and dropping the partitions of the draw, without locking the partitions
+//! of draws still taking wagers. Wagers and refunds are deleted in bounded batches.
+
+use std::sync::Arc;
+use chrono::{Duration, Utc};
+use tokio::sync::Mutex;
+use tokio_cron_scheduler::{Job, JobScheduler};
+use tracing::{error, info};
+use crate::config::app_config::RetentionConfig;
+use crate::db::{partition, retention, Pool};
+
+/// Number of expired draws fetched at a time.
+const DRAW_BATCH_SIZE: i64 = 100;
+
+/// What one retention run deleted.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RetentionSummary {
+    pub draws: u64,
+    pub partitions: u64,
+    pub wagers: u64,
+    pub refunds: u64,
+}
+
+pub struct RetentionJob;
+
+impl RetentionJob {
+    /// Deletes finalized and cancelled draws drawn more than `max_age_days` ago, oldest first,
+    /// with the wagers whose last draw they are.
+    pub async fn run(pool: &Pool, config: &RetentionConfig) -> Result<RetentionSummary, String> {
+        let cutoff = Utc::now() - Duration::days(config.max_age_days as i64);
+        let mut client = pool.get().await.map_err(|e| e.to_string())?;
+        let mut summary = RetentionSummary::default();
+
+        loop {
+            let draw_ids = retention::get_expired_draws(&client, cutoff, DRAW_BATCH_SIZE)
+                .await
+                .map_err(|e| e.to_string())?;
+            if draw_ids.is_empty() {
+                break;
+            }
+            for draw_id in draw_ids {
+                loop {
+                    let deleted = retention::delete_draw_refunds_batch(&client, draw_id, config.batch_size)
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    summary.refunds += deleted;
+                    if deleted == 0 {
+                        break;
+                    }
+                }
+                loop {
+                    let deleted = retention::delete_wager_refunds_batch(&client, draw_id, config.batch_size)
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    summary.refunds += deleted;
+                    if deleted == 0 {
+                        break;
+                    }
+                }
+                summary.partitions += partition::drop_draw_partitions(&client, draw_id).await.map_err(|e| e.to_string())?;
+                loop {
+                    let deleted = retention::delete_wagers_batch(&client, draw_id, config.batch_size)
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    summary.wagers += deleted;
+                    if deleted == 0 {
+                        break;
+                    }
+                }
+                let transaction = client.transaction().await.map_err(|e| e.to_string())?;
+                summary.draws += retention::delete_draw(&transaction, draw_id).await.map_err(|e| e.to_string())?;
+                transaction.commit().await.map_err(|e| e.to_string())?;
+                info!("Deleted expired draw {}", draw_id);
+            }
+        }
+        Ok(summary)
+    }
+
+    /// Runs the retention job on its configured schedule.
+    pub async fn schedule(pool: Pool, config: RetentionConfig) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let sched = JobScheduler::new().await?;
+        // A run still going when the next one fires causes that one to be skipped
+        let running = Arc::new(Mutex::new(()));
+        let schedule = config.schedule.clone();
+
+        let job = Job::new_async(schedule.as_str(), move |_uuid, _l| {
+            let pool = pool.clone();
+            let config = config.clone();
+            let running = running.clone();
+            Box::pin(async move {
+                let Ok(_running) = running.try_lock() else {
+                    info!("Previous retention run still running, skipping this one.");
+                    return;
+                };
+                info!("Cron job triggered: Deleting draws older than {} days.", config.max_age_days);
+                match RetentionJob::run(&pool, &config).await {
+                    Ok(summary) => info!(
+                        "Retention deleted {} draws, {} partitions, {} wagers and {} refunds",
+                        summary.draws, summary.partitions, summary.wagers, summary.refunds
+                    ),
+                    Err(e) => error!("Retention run failed: {}", e),
+                }
+            })
+        })?;
+        sched.add(job).await?;
+
+        sched.start().await?;
+        Ok(())
+    }
+}
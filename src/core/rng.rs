@@ -9,6 +9,13 @@ impl Rng {
         Rng(Xoshiro512StarStar::from_seed(rand_xoshiro::Seed512(seed)))
     }
 
+    /// Creates a generator seeded from the operating system's secure random source.
+    pub fn from_secure_seed() -> Result<Self, getrandom::Error> {
+        let mut seed = [0u8; 64];
+        getrandom::fill(&mut seed)?;
+        Ok(Rng::new(seed))
+    }
+
     pub fn next_u64(&mut self) -> u64 {
         self.0.next_u64()
     }
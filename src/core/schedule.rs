@@ -0,0 +1,185 @@
+//! Draw schedules: computes draw times from the schedule configuration of a game. Calendar
+//! schedules are evaluated in the game's timezone, so a draw stays at the same local time
+//! across daylight saving changes. Schedule exceptions skip, move and add draws on top of the
+//! regular schedule.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use crate::config::app_config::{ScheduleConfig, ScheduleExceptionConfig};
+
+fn parse_time(time: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(time, "%H:%M").map_err(|e| format!("Invalid draw time '{}': {}", time, e))
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| format!("Invalid date '{}': {}", date, e))
+}
+
+fn parse_local_date_time(date_time: &str, timezone: Tz) -> Result<DateTime<Utc>, String> {
+    let local = NaiveDateTime::parse_from_str(date_time, "%Y-%m-%d %H:%M")
+        .map_err(|e| format!("Invalid date and time '{}': {}", date_time, e))?;
+    local_to_utc(timezone, local.date(), local.time()).ok_or_else(|| format!("Invalid local time '{}' in {}", date_time, timezone))
+}
+
+/// Draw times of each weekday, sorted, indexed from Monday.
+fn weekday_times(schedule: &ScheduleConfig) -> Result<[Vec<NaiveTime>; 7], String> {
+    let mut times: [Vec<NaiveTime>; 7] = Default::default();
+    match schedule {
+        ScheduleConfig::Daily { time } => {
+            let time = parse_time(time)?;
+            for day_times in &mut times {
+                day_times.push(time);
+            }
+        },
+        ScheduleConfig::Weekly { days } => {
+            for day in days {
+                let weekday = day.day.parse::<Weekday>().map_err(|_| format!("Invalid weekday '{}'", day.day))?;
+                for time in &day.times {
+                    times[weekday.num_days_from_monday() as usize].push(parse_time(time)?);
+                }
+            }
+        },
+        ScheduleConfig::Interval { .. } => {},
+    }
+    for day_times in &mut times {
+        day_times.sort();
+        day_times.dedup();
+    }
+    Ok(times)
+}
+
+/// Resolves a local time to UTC. A time skipped by a daylight saving change moves forward by
+/// the length of the gap, and a repeated time resolves to its first occurrence.
+fn local_to_utc(timezone: Tz, date: NaiveDate, time: NaiveTime) -> Option<DateTime<Utc>> {
+    let local = date.and_time(time);
+    timezone
+        .from_local_datetime(&local)
+        .earliest()
+        .or_else(|| timezone.from_local_datetime(&(local + Duration::hours(1))).earliest())
+        .map(|t| t.with_timezone(&Utc))
+}
+
+/// Returns the first draw time of the schedule strictly after `after`.
+pub fn next_draw_time(schedule: &ScheduleConfig, timezone: Tz, after: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    if let ScheduleConfig::Interval { minutes } = schedule {
+        let seconds = minutes.saturating_mul(60) as i64;
+        if seconds == 0 {
+            return Err("Draw interval must be at least one minute".to_string());
+        }
+        let next = (after.timestamp().div_euclid(seconds) + 1) * seconds;
+        return DateTime::from_timestamp(next, 0).ok_or_else(|| format!("Draw time out of range after {}", after));
+    }
+
+    let times = weekday_times(schedule)?;
+    if times.iter().all(|day_times| day_times.is_empty()) {
+        return Err("Schedule has no draw times".to_string());
+    }
+    // Start a day early, as a time skipped by a daylight saving change may resolve past `after`
+    let mut date = after.with_timezone(&timezone).date_naive() - Duration::days(1);
+    for _ in 0..9 {
+        for time in &times[date.weekday().num_days_from_monday() as usize] {
+            if let Some(draw_time) = local_to_utc(timezone, date, *time)
+                && draw_time > after
+            {
+                return Ok(draw_time);
+            }
+        }
+        date += Duration::days(1);
+    }
+    Err(format!("No draw time found after {}", after))
+}
+
+/// Checks that a schedule parses and has draw times.
+pub fn validate_schedule(schedule: &ScheduleConfig, timezone: Tz) -> Result<(), String> {
+    next_draw_time(schedule, timezone, Utc::now()).map(|_| ())
+}
+
+/// A draw planned by the schedule. Open and close times are only set for extra draws that
+/// define them; other draws open when created and close `closed_state_duration_seconds`
+/// before the draw.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedDraw {
+    pub draw_time: DateTime<Utc>,
+    pub open_time: Option<DateTime<Utc>>,
+    pub close_time: Option<DateTime<Utc>>,
+}
+
+/// Whether a skip or move exception takes the regular draw at `draw_time` out of the schedule.
+fn removes_draw(exception: &ScheduleExceptionConfig, timezone: Tz, draw_time: DateTime<Utc>) -> Result<bool, String> {
+    let (ScheduleExceptionConfig::Skip { date, time } | ScheduleExceptionConfig::Move { date, time, .. }) = exception else {
+        return Ok(false);
+    };
+    let local = draw_time.with_timezone(&timezone);
+    if local.date_naive() != parse_date(date)? {
+        return Ok(false);
+    }
+    match time {
+        Some(time) => Ok(local.time() == parse_time(time)?),
+        None => Ok(true),
+    }
+}
+
+/// Parses and checks a schedule exception. An extra draw must close after it opens and no
+/// later than its draw time.
+pub fn validate_exception(exception: &ScheduleExceptionConfig, timezone: Tz) -> Result<(), String> {
+    match exception {
+        ScheduleExceptionConfig::Skip { date, time } => {
+            parse_date(date)?;
+            time.as_deref().map(parse_time).transpose()?;
+        },
+        ScheduleExceptionConfig::Move { date, time, to } => {
+            parse_date(date)?;
+            time.as_deref().map(parse_time).transpose()?;
+            parse_local_date_time(to, timezone)?;
+        },
+        ScheduleExceptionConfig::Extra { draw_time, open_time, close_time } => {
+            let draw_time = parse_local_date_time(draw_time, timezone)?;
+            let open_time = open_time.as_deref().map(|t| parse_local_date_time(t, timezone)).transpose()?;
+            let close_time = close_time.as_deref().map(|t| parse_local_date_time(t, timezone)).transpose()?;
+            if close_time.is_some_and(|close_time| close_time > draw_time) {
+                return Err("Extra draw closes after its draw time".to_string());
+            }
+            if let (Some(open_time), Some(close_time)) = (open_time, close_time)
+                && open_time >= close_time
+            {
+                return Err("Extra draw closes before it opens".to_string());
+            }
+        },
+    }
+    Ok(())
+}
+
+/// Returns the first draw strictly after `after`: the next regular draw not skipped or moved
+/// by an exception, or an earlier moved or extra draw.
+pub fn next_planned_draw(schedule: &ScheduleConfig, timezone: Tz, exceptions: &[ScheduleExceptionConfig], after: DateTime<Utc>) -> Result<PlannedDraw, String> {
+    let mut draw_time = next_draw_time(schedule, timezone, after)?;
+    let mut removed = 0;
+    while exceptions.iter().map(|e| removes_draw(e, timezone, draw_time)).collect::<Result<Vec<bool>, String>>()?.contains(&true) {
+        removed += 1;
+        if removed > 10_000 {
+            return Err(format!("Schedule exceptions remove all draws after {}", after));
+        }
+        draw_time = next_draw_time(schedule, timezone, draw_time)?;
+    }
+
+    let mut planned = PlannedDraw { draw_time, open_time: None, close_time: None };
+    for exception in exceptions {
+        let candidate = match exception {
+            ScheduleExceptionConfig::Skip { .. } => continue,
+            ScheduleExceptionConfig::Move { to, .. } => PlannedDraw {
+                draw_time: parse_local_date_time(to, timezone)?,
+                open_time: None,
+                close_time: None,
+            },
+            ScheduleExceptionConfig::Extra { draw_time, open_time, close_time } => PlannedDraw {
+                draw_time: parse_local_date_time(draw_time, timezone)?,
+                open_time: open_time.as_deref().map(|t| parse_local_date_time(t, timezone)).transpose()?,
+                close_time: close_time.as_deref().map(|t| parse_local_date_time(t, timezone)).transpose()?,
+            },
+        };
+        if candidate.draw_time > after && candidate.draw_time < planned.draw_time {
+            planned = candidate;
+        }
+    }
+    Ok(planned)
+}
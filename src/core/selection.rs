@@ -6,4 +6,17 @@ pub struct Selection {
     pub id: Uuid,
     pub name: String,
     pub values: Vec<i32>,
+    /// Whether each of `values` was machine-picked by quick pick, in the same order
+    pub machine_picked: Vec<bool>,
+}
+
+impl Selection {
+    /// Sorts the values into their canonical ascending order, keeping each machine-picked
+    /// flag with its value.
+    pub fn canonicalise(&mut self) {
+        self.machine_picked.resize(self.values.len(), false);
+        let mut values: Vec<(i32, bool)> = self.values.iter().copied().zip(self.machine_picked.iter().copied()).collect();
+        values.sort_unstable();
+        (self.values, self.machine_picked) = values.into_iter().unzip();
+    }
 }
@@ -0,0 +1,163 @@
+//! Normal and system boards: expansion of a board into the rows it plays, wager class
+//! lookup, and stake and price calculation.
+//!
+//! A board plays every combination of its selected numbers, taken as many at a time as its
+//! draw level draws. A normal board therefore plays a single row, while e.g. a system board
+//! with 8 primary numbers in a 6 number game plays C(8, 6) = 28 rows.
+
+use crate::config::app_config::WagerClassConfig;
+use crate::core::board::{Board, GameType};
+use crate::core::draw_level::DrawLevel;
+use crate::core::selection::Selection;
+
+/// Number of ways to choose `k` items out of `n`.
+pub fn binomial(n: usize, k: usize) -> u64 {
+    if k > n {
+        return 0;
+    }
+    let k = k.min(n - k);
+    (0..k).fold(1u64, |acc, i| acc * (n - i) as u64 / (i + 1) as u64)
+}
+
+/// All combinations of `k` values, in the order of `values`.
+pub fn combinations(values: &[i32], k: usize) -> Vec<Vec<i32>> {
+    if k > values.len() {
+        return Vec::new();
+    }
+    let mut result = Vec::new();
+    let mut indices: Vec<usize> = (0..k).collect();
+    loop {
+        result.push(indices.iter().map(|&i| values[i]).collect());
+        // Advance the rightmost index that still has room to move
+        let Some(position) = (0..k).rev().find(|&i| indices[i] < values.len() - k + i) else {
+            return result;
+        };
+        indices[position] += 1;
+        for i in position + 1..k {
+            indices[i] = indices[i - 1] + 1;
+        }
+    }
+}
+
+/// Row size of a selection: the number of numbers its draw level draws, or all of its values
+/// when it has no more than that.
+fn row_size(selection: &Selection, draw_levels: &[DrawLevel]) -> usize {
+    draw_levels
+        .iter()
+        .find(|level| level.name == selection.name)
+        .map(|level| (level.number_of_selections as usize).min(selection.values.len()))
+        .unwrap_or(selection.values.len())
+}
+
+/// Number of rows a board plays.
+pub fn row_count(board: &Board, draw_levels: &[DrawLevel]) -> u64 {
+    board
+        .selections
+        .iter()
+        .map(|selection| binomial(selection.values.len(), row_size(selection, draw_levels)))
+        .product()
+}
+
+/// Expands a board into the rows it plays. Each row has the same selections as the board,
+/// narrowed down to one combination of their values. Machine-picked flags are not carried over.
+pub fn expand_rows(board: &Board, draw_levels: &[DrawLevel]) -> Vec<Vec<Selection>> {
+    let mut rows: Vec<Vec<Selection>> = vec![Vec::new()];
+    for selection in &board.selections {
+        let selection_rows = combinations(&selection.values, row_size(selection, draw_levels));
+        rows = rows
+            .into_iter()
+            .flat_map(|row| {
+                selection_rows.iter().map(move |values| {
+                    let mut row = row.clone();
+                    row.push(Selection {
+                        id: selection.id,
+                        name: selection.name.clone(),
+                        values: values.clone(),
+                        machine_picked: Vec::new(),
+                    });
+                    row
+                })
+            })
+            .collect();
+    }
+    rows
+}
+
+fn collect_hit_distribution(counts: &[usize], misses: usize, row_size: usize, hits: &mut Vec<u32>, rows: u64, out: &mut Vec<(Vec<u32>, u64)>) {
+    let picked: usize = hits.iter().map(|h| *h as usize).sum();
+    match counts.split_first() {
+        None => {
+            if row_size >= picked && row_size - picked <= misses {
+                out.push((hits.clone(), rows * binomial(misses, row_size - picked)));
+            }
+        }
+        Some((&count, rest)) => {
+            for j in 0..=count.min(row_size.saturating_sub(picked)) {
+                hits.push(j as u32);
+                collect_hit_distribution(rest, misses, row_size, hits, rows * binomial(count, j), out);
+                hits.pop();
+            }
+        }
+    }
+}
+
+/// Counts hits per row without expanding the rows. For a selection whose values hit
+/// `counts[i]` winning numbers of each matched draw level and `misses` none, returns how many
+/// of its rows of `row_size` values get each combination of hits.
+pub fn hit_distribution(counts: &[usize], misses: usize, row_size: usize) -> Vec<(Vec<u32>, u64)> {
+    let mut distribution = Vec::new();
+    collect_hit_distribution(counts, misses, row_size, &mut Vec::new(), 1, &mut distribution);
+    distribution.retain(|(_, rows)| *rows > 0);
+    distribution
+}
+
+/// Name of the wager class of a board: "normal" for normal boards, "systemN" for system
+/// boards with N primary numbers.
+pub fn wager_class_name(board: &Board) -> String {
+    match board.game_type {
+        GameType::NORMAL => "normal".to_string(),
+        GameType::SYSTEM => {
+            let primary_selection = board.selections.iter().find(|s| s.name == "primary");
+            let count = primary_selection.map(|s| s.values.len()).unwrap_or(0);
+            format!("system{}", count)
+        }
+    }
+}
+
+pub fn find_wager_class<'a>(board: &Board, wager_classes: &'a [WagerClassConfig]) -> Result<&'a WagerClassConfig, String> {
+    let name = wager_class_name(board);
+    wager_classes
+        .iter()
+        .find(|wc| wc.name == name)
+        .ok_or_else(|| format!("Invalid game type or unmatched wager class: {}", name))
+}
+
+/// Stake of a board in one draw. A requested stake of 0 means the minimum stake of the
+/// wager class; any other stake must be within the class bounds and a whole number of
+/// increments above the minimum.
+pub fn board_stake(wager_class: &WagerClassConfig, requested_stake: u32) -> Result<u32, String> {
+    if requested_stake == 0 {
+        return Ok(wager_class.stake_min);
+    }
+    if requested_stake < wager_class.stake_min || requested_stake > wager_class.stake_max {
+        return Err(format!(
+            "Stake {} is not between {} and {} for wager class '{}'",
+            requested_stake, wager_class.stake_min, wager_class.stake_max, wager_class.name
+        ));
+    }
+    if wager_class.stake_increment > 0 && !(requested_stake - wager_class.stake_min).is_multiple_of(wager_class.stake_increment) {
+        return Err(format!(
+            "Stake {} is not a multiple of {} above {} for wager class '{}'",
+            requested_stake, wager_class.stake_increment, wager_class.stake_min, wager_class.name
+        ));
+    }
+    Ok(requested_stake)
+}
+
+/// Price of a wager: its stake in one draw times the number of draws it participates in.
+pub fn wager_price(stake: u32, draws: usize) -> Result<u32, String> {
+    u32::try_from(draws)
+        .ok()
+        .and_then(|draws| stake.checked_mul(draws))
+        .ok_or_else(|| format!("Price of stake {} in {} draws is too large", stake, draws))
+}
@@ -0,0 +1,138 @@
+//! Validation of board selections against the wager class and draw levels of a game, of the
+//! draws a wager participates in, and of wager cancellations.
+
+use crate::config::app_config::{DrawLevelConfig, WagerClassConfig};
+use crate::core::board::Board;
+use chrono::{DateTime, Duration, Utc};
+use crate::core::draw::{Draw, DrawStatus};
+use crate::core::wager::{Wager, WagerStatus};
+
+/// Canonicalises the selections of a board and validates them: every selection of the wager
+/// class is present with the required number of values, no other selections are present,
+/// values are within the range of their draw level and unique, and a dependent level does not
+/// repeat numbers of its parent level.
+pub fn validate_board(board: &mut Board, wager_class: &WagerClassConfig, draw_levels: &[DrawLevelConfig]) -> Result<(), String> {
+    for (sel_name, required_count) in wager_class.selections.iter().zip(&wager_class.number_of_selections) {
+        let Some(actual) = board.selections.iter().find(|s| &s.name == sel_name) else {
+            return Err(format!("Missing selection for '{}'", sel_name));
+        };
+        if actual.values.len() != *required_count as usize {
+            return Err(format!(
+                "Invalid number of values for '{}': expected {}, got {}",
+                sel_name, required_count, actual.values.len()
+            ));
+        }
+    }
+
+    // Extra selections not allowed
+    for s in &board.selections {
+        if !wager_class.selections.contains(&s.name) {
+            return Err(format!("Selection '{}' is not allowed for game type '{}'", s.name, wager_class.name));
+        }
+    }
+
+    for selection in &mut board.selections {
+        let Some(level) = draw_levels.iter().find(|l| l.name == selection.name) else {
+            return Err(format!("Selection '{}' has no draw level", selection.name));
+        };
+        selection.canonicalise();
+        if let Some(value) = selection
+            .values
+            .iter()
+            .find(|v| **v < level.min_value as i32 || **v > level.max_value as i32)
+        {
+            return Err(format!(
+                "Value {} for '{}' is not between {} and {}",
+                value, selection.name, level.min_value, level.max_value
+            ));
+        }
+        if let Some(pair) = selection.values.windows(2).find(|pair| pair[0] == pair[1]) {
+            return Err(format!("Value {} is selected more than once for '{}'", pair[0], selection.name));
+        }
+    }
+
+    for level in draw_levels {
+        let Some(parent) = &level.dependent_on else {
+            continue;
+        };
+        let (Some(selection), Some(parent_selection)) = (
+            board.selections.iter().find(|s| s.name == level.name),
+            board.selections.iter().find(|s| &s.name == parent),
+        ) else {
+            continue;
+        };
+        if let Some(value) = selection.values.iter().find(|v| parent_selection.values.contains(v)) {
+            return Err(format!(
+                "Value {} for '{}' is already selected for '{}'",
+                value, selection.name, parent
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Validates the draws a wager participates in and returns them in draw order. The draws
+/// must be consecutive open draws, their number one of `allowed_participations`, and they
+/// start from the earliest open draw, or from the one after it if `allow_skipping_first_draw`
+/// is set. Every requested draw must be in `open_draws`.
+pub fn validate_participation(
+    open_draws: &[Draw],
+    requested: &[i32],
+    allowed_participations: &[u32],
+    allow_skipping_first_draw: bool,
+) -> Result<Vec<Draw>, String> {
+    if !allowed_participations.contains(&(requested.len() as u32)) {
+        return Err(format!(
+            "Participating in {} draws is not allowed, allowed numbers of draws are {:?}",
+            requested.len(),
+            allowed_participations
+        ));
+    }
+
+    let mut ordered: Vec<&Draw> = open_draws.iter().collect();
+    ordered.sort_by_key(|d| (d.draw_time, d.id));
+    let mut positions: Vec<usize> = Vec::with_capacity(requested.len());
+    for draw_id in requested {
+        let Some(position) = ordered.iter().position(|d| d.id == *draw_id) else {
+            return Err(format!("Draw {} is not open", draw_id));
+        };
+        if positions.contains(&position) {
+            return Err(format!("Draw {} is requested more than once", draw_id));
+        }
+        positions.push(position);
+    }
+    positions.sort_unstable();
+
+    let (Some(&first), Some(&last)) = (positions.first(), positions.last()) else {
+        return Err("No draws requested".to_string());
+    };
+    if last - first + 1 != positions.len() {
+        return Err("Requested draws must be consecutive open draws".to_string());
+    }
+    match first {
+        0 => {},
+        1 if allow_skipping_first_draw => {},
+        1 => return Err(format!("The earliest open draw {} may not be skipped", ordered[0].id)),
+        _ => return Err(format!("Wager must start from the earliest open draw {} or the one after it", ordered[0].id)),
+    }
+    Ok(positions.iter().map(|p| ordered[*p].clone()).collect())
+}
+
+/// Checks that a player may cancel a wager at `now`: the wager is active, it was placed at
+/// most `cancellation_minutes` ago and none of `draws`, the current state of its draws, has
+/// closed.
+pub fn validate_wager_cancellation(wager: &Wager, draws: &[Draw], cancellation_minutes: u32, now: DateTime<Utc>) -> Result<(), String> {
+    if wager.status != WagerStatus::Active {
+        return Err(format!("Wager {} is {}", wager.id, wager.status));
+    }
+    if cancellation_minutes == 0 {
+        return Err("Wagers of this game cannot be cancelled".to_string());
+    }
+    if now > wager.created_at + Duration::minutes(cancellation_minutes as i64) {
+        return Err(format!("Wagers can only be cancelled within {} minutes of placing them", cancellation_minutes));
+    }
+    if let Some(draw) = draws.iter().find(|d| d.status != DrawStatus::Open || d.close_time <= now) {
+        return Err(format!("Draw {} of the wager has closed", draw.id));
+    }
+    Ok(())
+}
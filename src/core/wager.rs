@@ -2,8 +2,15 @@ use super::draw::Draw;
 use super::board::Board;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString};
 use uuid::Uuid;
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Display, EnumString)]
+pub enum WagerStatus {
+    Active,
+    Cancelled,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Wager {
     pub id: Uuid,
@@ -12,5 +19,14 @@ pub struct Wager {
     pub boards: Vec<Board>,
     pub stake: u32,
     pub price: u32,
+    pub status: WagerStatus,
     pub created_at: DateTime<Utc>,
 }
+
+impl Wager {
+    /// The draw the wager participates in last, by draw time. The boards and selections of the
+    /// wager are stored in the partitions of this draw.
+    pub fn last_draw_id(&self) -> Option<i32> {
+        self.draws.iter().max_by_key(|d| (d.draw_time, d.id)).map(|d| d.id)
+    }
+}
@@ -5,6 +5,11 @@ use uuid::Uuid;
 pub struct Win {
     pub id: Uuid,
     pub wager_id: Uuid,
+    pub draw_id: i32,
+    pub board_id: Uuid,
     pub win_class_id: Uuid,
+    pub winning_rows: u32,
+    /// Stake of a single winning row
+    pub row_stake: u64,
     pub amount: u64,
 }
@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use strum_macros::{Display, EnumString};
+use crate::config::app_config::{DrawLevelConfig, WinClassConfig, WinClassTypeConfig};
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Display, EnumString)]
 pub enum WinClassType {
     Factor,
     Constant,
@@ -9,6 +11,13 @@ pub enum WinClassType {
     External,
 }
 
+/// Number of hits a row needs on one draw level to belong to a win class.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RequiredHits {
+    pub draw_level: String,
+    pub hits: u32,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct WinClass {
     pub id: Uuid,
@@ -20,4 +29,70 @@ pub struct WinClass {
     pub percentage: Option<u32>,
     pub min_cap: Option<u64>,
     pub max_cap: Option<u64>,
+    /// Classes are evaluated in ascending rank; a row wins only in the first matching class.
+    pub rank: i32,
+    pub required_hits: Vec<RequiredHits>,
+}
+
+impl WinClass {
+    /// Builds a win class from configuration, checking that its hit pattern refers to
+    /// configured draw levels and that the fields its type needs are present. The id is
+    /// derived from the game id and the class name, like draw level ids.
+    pub fn from_config(game_id: Uuid, rank: i32, config: &WinClassConfig, draw_levels: &[DrawLevelConfig]) -> Result<Self, String> {
+        if config.draw_levels.len() != config.hits.len() {
+            return Err(format!(
+                "Win class '{}' has {} draw levels but {} hit counts",
+                config.name, config.draw_levels.len(), config.hits.len()
+            ));
+        }
+        let mut required_hits = Vec::new();
+        for (draw_level, hits) in config.draw_levels.iter().zip(&config.hits) {
+            let level = draw_levels
+                .iter()
+                .find(|l| &l.name == draw_level)
+                .ok_or_else(|| format!("Win class '{}' refers to unknown draw level '{}'", config.name, draw_level))?;
+            if *hits > level.selections {
+                return Err(format!(
+                    "Win class '{}' requires {} hits on '{}', which only draws {} numbers",
+                    config.name, hits, draw_level, level.selections
+                ));
+            }
+            required_hits.push(RequiredHits { draw_level: draw_level.clone(), hits: *hits });
+        }
+
+        let win_class_type = match config.r#type {
+            WinClassTypeConfig::Factor if config.factor.is_none() => {
+                return Err(format!("Factor win class '{}' has no factor", config.name));
+            }
+            WinClassTypeConfig::Constant if config.constant.is_none() => {
+                return Err(format!("Constant win class '{}' has no constant", config.name));
+            }
+            WinClassTypeConfig::Percentage if !matches!(config.percentage, Some(1..=100)) => {
+                return Err(format!("Percentage win class '{}' needs a percentage between 1 and 100", config.name));
+            }
+            WinClassTypeConfig::Factor => WinClassType::Factor,
+            WinClassTypeConfig::Constant => WinClassType::Constant,
+            WinClassTypeConfig::Percentage => WinClassType::Percentage,
+            WinClassTypeConfig::External => WinClassType::External,
+        };
+        if let (Some(min_cap), Some(max_cap)) = (config.min_cap, config.max_cap)
+            && min_cap > max_cap
+        {
+            return Err(format!("Win class '{}' has min_cap {} above max_cap {}", config.name, min_cap, max_cap));
+        }
+
+        Ok(WinClass {
+            id: Uuid::new_v5(&game_id, config.name.as_bytes()),
+            game_id,
+            name: config.name.clone(),
+            r#type: win_class_type,
+            factor: config.factor,
+            constant: config.constant,
+            percentage: config.percentage,
+            min_cap: config.min_cap,
+            max_cap: config.max_cap,
+            rank,
+            required_hits,
+        })
+    }
 }
@@ -0,0 +1,141 @@
+//! Win sum calculation. All amounts are integers in the smallest currency unit and every
+//! division rounds down; the remainder left over after sharing a win class total between
+//! its winners is not paid out.
+
+use std::collections::HashMap;
+use serde::Serialize;
+use tokio_postgres::Transaction;
+use tracing::info;
+use uuid::Uuid;
+use crate::core::draw::{Draw, DrawStatus};
+use crate::core::win_class::{WinClass, WinClassType};
+use crate::db;
+use crate::db::win::WinClassWinners;
+
+/// How the total of a win class is divided between its winning rows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PayoutBasis {
+    /// Every winning row gets the same share of the total.
+    PerRow,
+    /// Winning rows share the total in proportion to their stake.
+    ByStake,
+}
+
+/// Total win sum of a win class in a draw and how it is shared.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassPayout {
+    pub total: u64,
+    pub basis: PayoutBasis,
+}
+
+/// Computes the capped total of a win class. Returns `None` for an external win class whose
+/// total has not been set yet. Classes without winners pay nothing, regardless of `min_cap`.
+pub fn class_payout(
+    win_class: &WinClass,
+    winning_rows: u64,
+    winning_stake: u64,
+    turnover: u64,
+    external_total: Option<u64>,
+) -> Option<ClassPayout> {
+    let (uncapped, basis) = match win_class.r#type {
+        WinClassType::Factor => (
+            winning_stake as u128 * win_class.factor.unwrap_or(0) as u128,
+            PayoutBasis::ByStake,
+        ),
+        WinClassType::Constant => (
+            winning_rows as u128 * win_class.constant.unwrap_or(0) as u128,
+            PayoutBasis::PerRow,
+        ),
+        WinClassType::Percentage => (
+            turnover as u128 * win_class.percentage.unwrap_or(0) as u128 / 100,
+            PayoutBasis::PerRow,
+        ),
+        WinClassType::External => (external_total? as u128, PayoutBasis::PerRow),
+    };
+
+    if winning_rows == 0 {
+        return Some(ClassPayout { total: 0, basis });
+    }
+
+    let mut total = uncapped.min(u64::MAX as u128) as u64;
+    if let Some(min_cap) = win_class.min_cap {
+        total = total.max(min_cap);
+    }
+    if let Some(max_cap) = win_class.max_cap {
+        total = total.min(max_cap);
+    }
+    Some(ClassPayout { total, basis })
+}
+
+/// Checks that the total of a win class may be set for a draw: the class is an external win
+/// class of the draw's game and the draw is waiting for win sums in WinsetCalculated.
+pub fn validate_external_total(draw: &Draw, win_class: &WinClass) -> Result<(), String> {
+    if win_class.game_id != draw.game_id {
+        return Err(format!("Win class '{}' is not a win class of the game of draw {}", win_class.name, draw.id));
+    }
+    if win_class.r#type != WinClassType::External {
+        return Err(format!("Win class '{}' is not an external win class", win_class.name));
+    }
+    if draw.status != DrawStatus::WinsetCalculated {
+        return Err(format!("Draw {} is {:?}, expected WinsetCalculated", draw.id, draw.status));
+    }
+    Ok(())
+}
+
+/// Amount paid for one winning row when the total is shared equally between the rows.
+pub fn amount_per_row(payout: &ClassPayout, winning_rows: u64) -> u64 {
+    payout.total.checked_div(winning_rows).unwrap_or(0)
+}
+
+/// Outcome of a win sum calculation: the total amount of the wins per win class.
+#[derive(Debug, Default, Serialize)]
+pub struct WinSumSummary {
+    pub totals: HashMap<Uuid, u64>,
+}
+
+/// Computes the win amounts of all wins of a draw with a calculated winset. Returns `Ok(None)`
+/// without changing anything while an external win class with winners has no total set for
+/// the draw. The calculation can be repeated; it always recomputes every amount. The amounts
+/// are written in the transaction that confirms the wins, with the draw locked.
+pub async fn calculate_win_sums(
+    transaction: &Transaction<'_>,
+    draw: &Draw,
+    win_classes: &[WinClass],
+) -> Result<Option<WinSumSummary>, Box<dyn std::error::Error + Send + Sync>> {
+    info!("Calculating win sums for draw {}", draw.id);
+    let winners: Vec<WinClassWinners> = db::win::get_win_class_winners(transaction, draw.id).await?;
+    let external_totals = db::win::get_external_win_totals(transaction, draw.id).await?;
+    let turnover = db::win::get_draw_turnover(transaction, draw.id).await?;
+
+    let mut payouts = Vec::new();
+    for class_winners in &winners {
+        let Some(win_class) = win_classes.iter().find(|c| c.id == class_winners.win_class_id) else {
+            return Err(format!("Draw {} has wins in unknown win class {}", draw.id, class_winners.win_class_id).into());
+        };
+        let external_total = external_totals.get(&win_class.id).copied();
+        match class_payout(win_class, class_winners.winning_rows, class_winners.winning_stake, turnover, external_total) {
+            Some(payout) => payouts.push((class_winners, payout)),
+            None => {
+                info!("Draw {} is waiting for the external total of win class '{}'", draw.id, win_class.name);
+                return Ok(None);
+            }
+        }
+    }
+
+    let mut summary = WinSumSummary::default();
+    for (class_winners, payout) in payouts {
+        let total = match payout.basis {
+            PayoutBasis::PerRow => {
+                let amount = amount_per_row(&payout, class_winners.winning_rows);
+                db::win::update_win_amounts_per_row(transaction, draw.id, class_winners.win_class_id, amount).await?
+            }
+            PayoutBasis::ByStake if class_winners.winning_stake > 0 => {
+                db::win::update_win_amounts_by_stake(transaction, draw.id, class_winners.win_class_id, payout.total, class_winners.winning_stake).await?
+            }
+            PayoutBasis::ByStake => db::win::update_win_amounts_per_row(transaction, draw.id, class_winners.win_class_id, 0).await?,
+        };
+        summary.totals.insert(class_winners.win_class_id, total);
+    }
+    info!("Calculated win sums for draw {}: {:?}", draw.id, summary.totals);
+    Ok(Some(summary))
+}
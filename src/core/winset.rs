@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use tokio_postgres::Client;
+use tracing::info;
+use uuid::Uuid;
+use crate::core::board::Board;
+use crate::core::draw::{Draw, DrawStatus, WinningNumbers};
+use crate::core::draw_level::DrawLevel;
+use crate::core::system_wager;
+use crate::core::win::Win;
+use crate::core::win_class::WinClass;
+use crate::db;
+
+/// Number of wagers fetched from the database per batch during winset calculation.
+pub const WINSET_BATCH_SIZE: i64 = 10_000;
+
+/// A board selection matched against the winning numbers of its draw level and of the levels
+/// depending on it. A dependent level (e.g. a secondary number drawn from the primary number
+/// space) is matched against its own selection when the board has one, and against its
+/// parent's selection otherwise.
+struct SelectionRule {
+    name: String,
+    row_size: usize,
+    level: usize,
+    dependents: Vec<usize>,
+}
+
+/// Winning numbers of all draw levels and the hit patterns of all win classes of a draw,
+/// prepared for evaluating boards one by one.
+pub struct WinsetRules {
+    draw_levels: Vec<DrawLevel>,
+    winning: Vec<Vec<bool>>,
+    selections: Vec<SelectionRule>,
+    classes: Vec<(Uuid, Vec<Option<u32>>)>,
+}
+
+impl WinsetRules {
+    pub fn new(draw_levels: &[DrawLevel], winning_numbers: &[WinningNumbers], win_classes: &[WinClass]) -> Result<Self, String> {
+        let mut winning = Vec::new();
+        for level in draw_levels {
+            let numbers = winning_numbers
+                .iter()
+                .find(|w| w.draw_level_id == level.id)
+                .ok_or_else(|| format!("No winning numbers for draw level '{}'", level.name))?;
+            let mut lookup = vec![false; level.max_value as usize + 1];
+            for number in &numbers.numbers {
+                if let Some(slot) = lookup.get_mut(*number as usize) {
+                    *slot = true;
+                }
+            }
+            winning.push(lookup);
+        }
+
+        let mut selections: Vec<SelectionRule> = Vec::new();
+        for (index, level) in draw_levels.iter().enumerate() {
+            match &level.dependent_on {
+                Some(parent) => match selections.iter_mut().find(|s| &s.name == parent) {
+                    Some(rule) => rule.dependents.push(index),
+                    None => return Err(format!("Draw level '{}' depends on unknown level '{}'", level.name, parent)),
+                },
+                None => selections.push(SelectionRule {
+                    name: level.name.clone(),
+                    row_size: level.number_of_selections as usize,
+                    level: index,
+                    dependents: Vec::new(),
+                }),
+            }
+        }
+
+        let mut sorted_classes: Vec<&WinClass> = win_classes.iter().collect();
+        sorted_classes.sort_by_key(|c| c.rank);
+        let mut classes = Vec::new();
+        for class in sorted_classes {
+            let mut pattern = vec![None; draw_levels.len()];
+            for required in &class.required_hits {
+                let index = draw_levels
+                    .iter()
+                    .position(|l| l.name == required.draw_level)
+                    .ok_or_else(|| format!("Win class '{}' refers to unknown draw level '{}'", class.name, required.draw_level))?;
+                pattern[index] = Some(required.hits);
+            }
+            classes.push((class.id, pattern));
+        }
+
+        Ok(WinsetRules { draw_levels: draw_levels.to_vec(), winning, selections, classes })
+    }
+
+    /// Stake of a single row of a board.
+    pub fn row_stake(&self, board: &Board) -> u64 {
+        (board.stake as u64).checked_div(system_wager::row_count(board, &self.draw_levels)).unwrap_or(0)
+    }
+
+    /// Returns the winning rows of a board per win class. A normal board has a single row;
+    /// a system board has one row for every combination of its numbers.
+    pub fn board_wins(&self, board: &Board) -> Vec<(Uuid, u32)> {
+        // Hits per draw level together with the number of rows having exactly those hits
+        let mut rows: Vec<(Vec<u32>, u64)> = vec![(vec![0; self.winning.len()], 1)];
+
+        // Selection values matched against the winning numbers of one or more draw levels,
+        // together with the row size of the selection
+        let mut groups: Vec<(&[i32], usize, Vec<usize>)> = Vec::new();
+        for rule in &self.selections {
+            let mut levels = vec![rule.level];
+            for &dependent in &rule.dependents {
+                let level = &self.draw_levels[dependent];
+                match board.selections.iter().find(|s| s.name == level.name) {
+                    Some(selection) => groups.push((&selection.values, level.number_of_selections as usize, vec![dependent])),
+                    None => levels.push(dependent),
+                }
+            }
+            let values: &[i32] = board
+                .selections
+                .iter()
+                .find(|s| s.name == rule.name)
+                .map(|s| s.values.as_slice())
+                .unwrap_or(&[]);
+            groups.push((values, rule.row_size, levels));
+        }
+
+        for (values, row_size, levels) in groups {
+            let counts: Vec<usize> = levels
+                .iter()
+                .map(|&level| {
+                    values
+                        .iter()
+                        .filter(|&&v| v >= 0 && self.winning[level].get(v as usize).copied().unwrap_or(false))
+                        .count()
+                })
+                .collect();
+            let misses = values.len() - counts.iter().sum::<usize>();
+            let row_size = row_size.min(values.len());
+
+            let distribution = system_wager::hit_distribution(&counts, misses, row_size);
+
+            let mut combined = Vec::new();
+            for (hits, count) in &rows {
+                for (selection_hits, selection_count) in &distribution {
+                    let mut all_hits = hits.clone();
+                    for (level, h) in levels.iter().zip(selection_hits) {
+                        all_hits[*level] = *h;
+                    }
+                    combined.push((all_hits, count * selection_count));
+                }
+            }
+            rows = combined;
+        }
+
+        let mut wins: Vec<(Uuid, u32)> = Vec::new();
+        for (hits, count) in rows {
+            let matching = self.classes.iter().find(|(_, pattern)| {
+                pattern.iter().zip(&hits).all(|(required, actual)| required.is_none_or(|r| r == *actual))
+            });
+            if let Some((class_id, _)) = matching {
+                match wins.iter_mut().find(|(id, _)| id == class_id) {
+                    Some((_, rows)) => *rows += count as u32,
+                    None => wins.push((*class_id, count as u32)),
+                }
+            }
+        }
+        wins
+    }
+}
+
+/// Outcome of a winset calculation.
+#[derive(Debug, Default)]
+pub struct WinsetSummary {
+    pub boards: u64,
+    pub winning_rows: HashMap<Uuid, u64>,
+}
+
+/// Calculates the winset of a drawn draw: streams all wagers participating in the draw
+/// with their boards in batches, matches them against the draw's winning numbers and the game's win
+/// classes, and writes the winning boards to the `win` table. Any previously calculated
+/// winset of the draw is replaced. Win sums are not calculated here. The calculation fails
+/// without writing further wins once the draw is no longer Drawn, e.g. when it is cancelled.
+pub async fn calculate_winset(
+    client: &mut Client,
+    draw: &Draw,
+    draw_levels: &[DrawLevel],
+) -> Result<WinsetSummary, Box<dyn std::error::Error + Send + Sync>> {
+    info!("Calculating winset for draw {}", draw.id);
+    if draw.winning_numbers.is_empty() {
+        return Err(format!("Draw {} has no winning numbers", draw.id).into());
+    }
+
+    db::win::delete_wins_for_draw(&*client, draw.id).await?;
+    let win_classes = db::win_class::get_win_classes(client, draw.game_id).await?;
+    let rules = WinsetRules::new(draw_levels, &draw.winning_numbers, &win_classes)?;
+
+    let mut summary = WinsetSummary::default();
+    let mut after_wager_id = Uuid::nil();
+    loop {
+        let wagers = db::wager::get_draw_wagers_batch(client, draw.id, after_wager_id, WINSET_BATCH_SIZE).await?;
+        let Some(last_wager) = wagers.last() else {
+            break;
+        };
+        let last_wager_id = last_wager.id;
+
+        let mut wins = Vec::new();
+        for wager in &wagers {
+            for board in &wager.boards {
+                let row_stake = rules.row_stake(board);
+                for (win_class_id, winning_rows) in rules.board_wins(board) {
+                    *summary.winning_rows.entry(win_class_id).or_default() += winning_rows as u64;
+                    wins.push(Win {
+                        id: Uuid::now_v7(),
+                        wager_id: wager.id,
+                        draw_id: draw.id,
+                        board_id: board.id,
+                        win_class_id,
+                        winning_rows,
+                        row_stake,
+                        amount: 0,
+                    });
+                }
+            }
+            summary.boards += wager.boards.len() as u64;
+        }
+
+        // The draw stays locked until the wins are committed, so a concurrent cancellation
+        // either removes them afterwards or is seen here
+        let transaction = client.transaction().await?;
+        if db::draw::lock_draw_status(&transaction, draw.id).await?.as_ref() != Some(&DrawStatus::Drawn) {
+            return Err(format!("Draw {} is no longer Drawn, stopping its winset calculation", draw.id).into());
+        }
+        db::win::insert_wins(&transaction, &wins).await?;
+        transaction.commit().await?;
+        after_wager_id = last_wager_id;
+    }
+
+    info!("Calculated winset for draw {}: {} boards, winning rows per class {:?}", draw.id, summary.boards, summary.winning_rows);
+    Ok(summary)
+}
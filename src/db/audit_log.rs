@@ -0,0 +1,88 @@
+use chrono::{DateTime, Utc};
+use tokio_postgres::{Client, Error, GenericClient, Row};
+use tracing::error;
+use crate::core::audit_log::AuditLog;
+
+/// Inserts an audit log entry, usually inside the transaction of the change it records.
+pub async fn insert_audit_log(client: &impl GenericClient, audit_log: &AuditLog) -> Result<(), Error> {
+    // JSON is passed as text and converted by Postgres
+    let data = audit_log.data.to_string();
+    client
+        .execute(
+            "INSERT INTO audit_log (id, entity_type, entity_id, event_type, data, created_at) VALUES ($1, $2, $3, $4, $5::TEXT::JSONB, $6)",
+            &[&audit_log.id, &audit_log.entity_type, &audit_log.entity_id, &audit_log.event_type, &data, &audit_log.created_at],
+        )
+        .await?;
+    Ok(())
+}
+
+/// Inserts a batch of audit log entries with a single statement.
+pub async fn insert_audit_logs(client: &impl GenericClient, audit_logs: &[AuditLog]) -> Result<u64, Error> {
+    if audit_logs.is_empty() {
+        return Ok(0);
+    }
+    let ids: Vec<_> = audit_logs.iter().map(|a| a.id).collect();
+    let entity_types: Vec<&str> = audit_logs.iter().map(|a| a.entity_type.as_str()).collect();
+    let entity_ids: Vec<&str> = audit_logs.iter().map(|a| a.entity_id.as_str()).collect();
+    let event_types: Vec<&str> = audit_logs.iter().map(|a| a.event_type.as_str()).collect();
+    let data: Vec<String> = audit_logs.iter().map(|a| a.data.to_string()).collect();
+    let created_at: Vec<_> = audit_logs.iter().map(|a| a.created_at).collect();
+    client
+        .execute(
+            "INSERT INTO audit_log (id, entity_type, entity_id, event_type, data, created_at)
+             SELECT id, entity_type, entity_id, event_type, data::JSONB, created_at
+             FROM UNNEST($1::uuid[], $2::text[], $3::text[], $4::text[], $5::text[], $6::timestamptz[])
+                 AS t(id, entity_type, entity_id, event_type, data, created_at)",
+            &[&ids, &entity_types, &entity_ids, &event_types, &data, &created_at],
+        )
+        .await
+}
+
+/// Criteria for querying the audit log. Unset criteria match every entry.
+#[derive(Debug, Clone, Default)]
+pub struct AuditLogFilter {
+    pub entity_type: Option<String>,
+    pub entity_id: Option<String>,
+    /// Inclusive lower bound of `created_at`
+    pub from: Option<DateTime<Utc>>,
+    /// Exclusive upper bound of `created_at`
+    pub to: Option<DateTime<Utc>>,
+    /// Maximum number of entries returned, all matching entries when unset
+    pub limit: Option<i64>,
+}
+
+fn audit_log_from_row(row: &Row) -> Option<AuditLog> {
+    let id = row.get("id");
+    let data = match serde_json::from_str(row.get("data")) {
+        Ok(data) => data,
+        Err(e) => {
+            error!("Invalid data in database for audit log entry {}: {}", id, e);
+            return None;
+        }
+    };
+    Some(AuditLog {
+        id,
+        entity_type: row.get("entity_type"),
+        entity_id: row.get("entity_id"),
+        event_type: row.get("event_type"),
+        data,
+        created_at: row.get("created_at"),
+    })
+}
+
+/// Fetches audit log entries matching the filter, oldest first.
+pub async fn get_audit_logs(client: &Client, filter: &AuditLogFilter) -> Result<Vec<AuditLog>, Error> {
+    let rows = client
+        .query(
+            "SELECT id, entity_type, entity_id, event_type, data::TEXT AS data, created_at FROM audit_log
+             WHERE ($1::VARCHAR IS NULL OR entity_type = $1)
+               AND ($2::VARCHAR IS NULL OR entity_id = $2)
+               AND ($3::TIMESTAMPTZ IS NULL OR created_at >= $3)
+               AND ($4::TIMESTAMPTZ IS NULL OR created_at < $4)
+             ORDER BY created_at, id
+             LIMIT $5",
+            &[&filter.entity_type, &filter.entity_id, &filter.from, &filter.to, &filter.limit],
+        )
+        .await?;
+    Ok(rows.iter().filter_map(audit_log_from_row).collect())
+}
@@ -1,119 +1,216 @@
-use tokio_postgres::{Client, Error};
+use tokio_postgres::{Client, Error, GenericClient, Row, Transaction};
 use uuid::Uuid;
 use tracing::{info, error};
-use crate::core::draw::{Draw, DrawStatus};
-use chrono::Utc;
+use crate::core::draw::{Draw, DrawStatus, WinningNumbers};
+use chrono::{DateTime, Utc};
 
-pub async fn get_active_draws(client: &Client, game_id: Uuid) -> Result<Vec<Draw>, Error> {
+const DRAW_COLUMNS: &str = "id, game_id, status, created_at, modified_at, open_time, close_time, draw_time, winset_calculated_at, winset_confirmed_at, winning_numbers::TEXT AS winning_numbers";
+
+fn draw_from_row(row: &Row) -> Option<Draw> {
+    let status_str: String = row.get("status");
+    let status = match status_str.parse::<DrawStatus>() {
+        Ok(status) => status,
+        Err(_) => {
+            error!("Unknown draw status in database: '{}'", status_str);
+            return None;
+        }
+    };
+
+    let winning_numbers_json: String = row.get("winning_numbers");
+    let winning_numbers = match serde_json::from_str::<Vec<WinningNumbers>>(&winning_numbers_json) {
+        Ok(winning_numbers) => winning_numbers,
+        Err(e) => {
+            error!("Invalid winning numbers in database for draw {}: {}", row.get::<_, i32>("id"), e);
+            return None;
+        }
+    };
+
+    Some(Draw {
+        id: row.get("id"),
+        game_id: row.get("game_id"),
+        status,
+        created_at: row.get("created_at"),
+        modified_at: row.get("modified_at"),
+        open_time: row.get("open_time"),
+        close_time: row.get("close_time"),
+        draw_time: row.get("draw_time"),
+        winset_calculated_at: row.get("winset_calculated_at"),
+        winset_confirmed_at: row.get("winset_confirmed_at"),
+        winning_numbers,
+    })
+}
+
+pub async fn get_active_draws(client: &impl GenericClient, game_id: Uuid) -> Result<Vec<Draw>, Error> {
     info!("Attempting to get active draws for game_id: {}", game_id);
     let rows = client
         .query(
-            "SELECT id, game_id, status, created_at, modified_at, open_time, close_time, draw_time, winset_calculated_at, winset_confirmed_at FROM draw WHERE game_id = $1 AND status IN ('Created', 'Open')",
+            &format!("SELECT {} FROM draw WHERE game_id = $1 AND status IN ('Created', 'Open')", DRAW_COLUMNS),
             &[&game_id],
         )
         .await?;
 
-    let mut draws = Vec::new();
-    for row in rows {
-        let status_str: String = row.get("status");
-        let status = match status_str.as_str() {
-            "Created" => DrawStatus::Created,
-            "Open" => DrawStatus::Open,
-            "Closed" => DrawStatus::Closed,
-            "Drawn" => DrawStatus::Drawn,
-            "WinsetCalculated" => DrawStatus::WinsetCalculated,
-            "WinsetConfirmed" => DrawStatus::WinsetConfirmed,
-            "Finalized" => DrawStatus::Finalized,
-            "Cancelled" => DrawStatus::Cancelled,
-            _ => {
-                error!("Unknown draw status in database: '{}'", status_str);
-                continue;
-            }
-        };
-
-        draws.push(Draw {
-            id: row.get("id"),
-            game_id: row.get("game_id"),
-            status,
-            created_at: row.get("created_at"),
-            modified_at: row.get("modified_at"),
-            open_time: row.get("open_time"),
-            close_time: row.get("close_time"),
-            draw_time: row.get("draw_time"),
-            winset_calculated_at: row.get("winset_calculated_at"),
-            winset_confirmed_at: row.get("winset_confirmed_at"),
-            winning_numbers: Vec::new(), // TODO: Deserialize winning_numbers
-        });
-    }
+    let draws: Vec<Draw> = rows.iter().filter_map(draw_from_row).collect();
     info!("Found {} active draws for game_id: {}", draws.len(), game_id);
     Ok(draws)
 }
 
-pub async fn insert_draw(client: &Client, draw: &Draw) -> Result<(), Error> {
+/// Inserts a draw and returns its id. The partitions of the draw are created separately by
+/// `partition::create_draw_partitions`, once the draw is committed.
+pub async fn insert_draw(client: &impl GenericClient, draw: &Draw) -> Result<i32, Error> {
     info!("Attempting to insert draw: {:?}", draw);
-    client
-        .execute(
-            "INSERT INTO draw (game_id, status, created_at, modified_at, open_time, close_time, draw_time) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+    let row = client
+        .query_one(
+            "INSERT INTO draw (game_id, status, created_at, modified_at, open_time, close_time, draw_time) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id",
             &[&draw.game_id, &draw.status.to_string(), &draw.created_at, &draw.modified_at, &draw.open_time, &draw.close_time, &draw.draw_time],
         )
         .await?;
     info!("Successfully inserted draw: {:?}", draw);
-    Ok(())
+    Ok(row.get("id"))
+}
+
+/// Fetches the draw times of all draws of a game, in any status, scheduled after `after`.
+pub async fn get_draw_times_after(client: &impl GenericClient, game_id: Uuid, after: DateTime<Utc>) -> Result<Vec<DateTime<Utc>>, Error> {
+    let rows = client
+        .query("SELECT draw_time FROM draw WHERE game_id = $1 AND draw_time > $2 ORDER BY draw_time", &[&game_id, &after])
+        .await?;
+    Ok(rows.iter().map(|row| row.get("draw_time")).collect())
+}
+
+/// Deletes the draws of a game that have not opened yet, so they can be planned again, and
+/// returns their ids. Created draws have no wagers, so their partitions can be dropped with
+/// `partition::drop_draw_partitions` once the deletion is committed.
+pub async fn delete_created_draws(client: &impl GenericClient, game_id: Uuid) -> Result<Vec<i32>, Error> {
+    info!("Attempting to delete created draws for game_id: {}", game_id);
+    let rows = client
+        .query("DELETE FROM draw WHERE game_id = $1 AND status = 'Created' RETURNING id", &[&game_id])
+        .await?;
+    let deleted: Vec<i32> = rows.iter().map(|row| row.get("id")).collect();
+    info!("Deleted {} created draws for game_id: {}", deleted.len(), game_id);
+    Ok(deleted)
 }
 
 pub async fn get_created_draws_ready_to_open(client: &Client, game_id: Uuid) -> Result<Vec<Draw>, Error> {
     info!("Attempting to get created draws ready to open for game_id: {}", game_id);
     let rows = client
         .query(
-            "SELECT id, game_id, status, created_at, modified_at, open_time, close_time, draw_time, winset_calculated_at, winset_confirmed_at FROM draw WHERE game_id = $1 AND status = 'Created' AND open_time <= $2",
+            &format!("SELECT {} FROM draw WHERE game_id = $1 AND status = 'Created' AND open_time <= $2", DRAW_COLUMNS),
             &[&game_id, &Utc::now()],
         )
         .await?;
 
-    let mut draws = Vec::new();
-    for row in rows {
-        let status_str: String = row.get("status");
-        let status = match status_str.as_str() {
-            "Created" => DrawStatus::Created,
-            "Open" => DrawStatus::Open,
-            "Closed" => DrawStatus::Closed,
-            "Drawn" => DrawStatus::Drawn,
-            "WinsetCalculated" => DrawStatus::WinsetCalculated,
-            "WinsetConfirmed" => DrawStatus::WinsetConfirmed,
-            "Finalized" => DrawStatus::Finalized,
-            "Cancelled" => DrawStatus::Cancelled,
-            _ => {
-                error!("Unknown draw status in database: '{}'", status_str);
-                continue;
-            }
-        };
-
-        draws.push(Draw {
-            id: row.get("id"),
-            game_id: row.get("game_id"),
-            status,
-            created_at: row.get("created_at"),
-            modified_at: row.get("modified_at"),
-            open_time: row.get("open_time"),
-            close_time: row.get("close_time"),
-            draw_time: row.get("draw_time"),
-            winset_calculated_at: row.get("winset_calculated_at"),
-            winset_confirmed_at: row.get("winset_confirmed_at"),
-            winning_numbers: Vec::new(), // TODO: Deserialize winning_numbers
-        });
-    }
+    let draws: Vec<Draw> = rows.iter().filter_map(draw_from_row).collect();
     info!("Found {} created draws ready to open for game_id: {}", draws.len(), game_id);
     Ok(draws)
 }
 
-pub async fn update_draw_status(client: &Client, draw_id: i32, new_status: DrawStatus) -> Result<(), Error> {
-    info!("Attempting to update draw {} status to {:?}", draw_id, new_status);
+pub async fn get_open_draws_ready_to_close(client: &Client, game_id: Uuid) -> Result<Vec<Draw>, Error> {
+    info!("Attempting to get open draws ready to close for game_id: {}", game_id);
+    let rows = client
+        .query(
+            &format!("SELECT {} FROM draw WHERE game_id = $1 AND status = 'Open' AND close_time <= $2 ORDER BY id", DRAW_COLUMNS),
+            &[&game_id, &Utc::now()],
+        )
+        .await?;
+
+    let draws: Vec<Draw> = rows.iter().filter_map(draw_from_row).collect();
+    info!("Found {} open draws ready to close for game_id: {}", draws.len(), game_id);
+    Ok(draws)
+}
+
+pub async fn get_closed_draws_ready_to_draw(client: &Client, game_id: Uuid) -> Result<Vec<Draw>, Error> {
+    info!("Attempting to get closed draws ready to draw for game_id: {}", game_id);
+    let rows = client
+        .query(
+            &format!("SELECT {} FROM draw WHERE game_id = $1 AND status = 'Closed' AND draw_time <= $2 ORDER BY id", DRAW_COLUMNS),
+            &[&game_id, &Utc::now()],
+        )
+        .await?;
+
+    let draws: Vec<Draw> = rows.iter().filter_map(draw_from_row).collect();
+    info!("Found {} closed draws ready to draw for game_id: {}", draws.len(), game_id);
+    Ok(draws)
+}
+
+pub async fn get_draws_by_status(client: &Client, game_id: Uuid, status: DrawStatus) -> Result<Vec<Draw>, Error> {
+    info!("Attempting to get draws in status {:?} for game_id: {}", status, game_id);
+    let rows = client
+        .query(
+            &format!("SELECT {} FROM draw WHERE game_id = $1 AND status = $2 ORDER BY id", DRAW_COLUMNS),
+            &[&game_id, &status.to_string()],
+        )
+        .await?;
+
+    let draws: Vec<Draw> = rows.iter().filter_map(draw_from_row).collect();
+    info!("Found {} draws in status {:?} for game_id: {}", draws.len(), status, game_id);
+    Ok(draws)
+}
+
+/// Fetches the open draws, optionally of a single game, ordered by id.
+pub async fn get_open_draws(client: &Client, game_id: Option<Uuid>) -> Result<Vec<Draw>, Error> {
+    let rows = client
+        .query(
+            &format!("SELECT {} FROM draw WHERE status = 'Open' AND ($1::UUID IS NULL OR game_id = $1) ORDER BY id", DRAW_COLUMNS),
+            &[&game_id],
+        )
+        .await?;
+    Ok(rows.iter().filter_map(draw_from_row).collect())
+}
+
+/// Fetches a draw by id, or `None` if it does not exist.
+pub async fn get_draw(client: &Client, draw_id: i32) -> Result<Option<Draw>, Error> {
+    let row = client
+        .query_opt(&format!("SELECT {} FROM draw WHERE id = $1", DRAW_COLUMNS), &[&draw_id])
+        .await?;
+    Ok(row.as_ref().and_then(draw_from_row))
+}
+
+/// Locks a draw row until the end of the transaction and returns its current status.
+pub async fn lock_draw_status(transaction: &Transaction<'_>, draw_id: i32) -> Result<Option<DrawStatus>, Error> {
+    let row = transaction
+        .query_opt("SELECT status FROM draw WHERE id = $1 FOR UPDATE", &[&draw_id])
+        .await?;
+    Ok(row.and_then(|row| row.get::<_, String>("status").parse::<DrawStatus>().ok()))
+}
+
+/// Fetches the draws a wager participates in, ordered by id, and locks them against status
+/// changes until the transaction ends, so they cannot close meanwhile.
+pub async fn lock_wager_draws(transaction: &Transaction<'_>, wager_id: Uuid) -> Result<Vec<Draw>, Error> {
+    let rows = transaction
+        .query(
+            &format!(
+                "SELECT {} FROM draw WHERE id IN (SELECT draw_id FROM draw_wager WHERE wager_id = $1) ORDER BY id FOR SHARE",
+                DRAW_COLUMNS
+            ),
+            &[&wager_id],
+        )
+        .await?;
+    Ok(rows.iter().filter_map(draw_from_row).collect())
+}
+
+/// Fetches the draws a wager participates in, ordered by id.
+pub async fn get_wager_draws(client: &Client, wager_id: Uuid) -> Result<Vec<Draw>, Error> {
+    let rows = client
+        .query(
+            &format!("SELECT {} FROM draw WHERE id IN (SELECT draw_id FROM draw_wager WHERE wager_id = $1) ORDER BY id", DRAW_COLUMNS),
+            &[&wager_id],
+        )
+        .await?;
+    Ok(rows.iter().filter_map(draw_from_row).collect())
+}
+
+/// Persists the status, all lifecycle timestamps and the winning numbers of a draw, as set by
+/// `DrawManager::transition_draw_status`, also inside a transaction.
+pub async fn update_draw(client: &impl GenericClient, draw: &Draw) -> Result<(), Error> {
+    info!("Attempting to update draw {} to status {:?}", draw.id, draw.status);
+    // Serializing plain ids and numbers cannot fail
+    let winning_numbers = serde_json::to_string(&draw.winning_numbers).expect("Winning numbers serialize to JSON");
     client
         .execute(
-            "UPDATE draw SET status = $1, modified_at = $2 WHERE id = $3",
-            &[&new_status.to_string(), &Utc::now(), &draw_id],
+            "UPDATE draw SET status = $1, modified_at = $2, draw_time = $3, winset_calculated_at = $4, winset_confirmed_at = $5,
+             winning_numbers = $6::TEXT::JSONB WHERE id = $7",
+            &[&draw.status.to_string(), &draw.modified_at, &draw.draw_time, &draw.winset_calculated_at, &draw.winset_confirmed_at, &winning_numbers, &draw.id],
         )
         .await?;
-    info!("Successfully updated draw {} status to {:?}", draw_id, new_status);
+    info!("Successfully updated draw {} to status {:?}", draw.id, draw.status);
     Ok(())
 }
@@ -1,8 +1,21 @@
+use std::time::Duration;
+use deadpool_postgres::{Manager, ManagerConfig, RecyclingMethod, Runtime};
 use refinery::{Error, Report};
+use tokio_postgres::NoTls;
+use crate::config::app_config::DatabaseConfig;
 
+pub use deadpool_postgres::Pool;
+
+pub mod audit_log;
 pub mod draw;
 pub mod operator;
+pub mod partition;
+pub mod refund;
+pub mod retention;
+pub mod schedule_exception;
 pub mod wager;
+pub mod win;
+pub mod win_class;
 
 mod migrations {
     use refinery::embed_migrations;
@@ -12,3 +25,22 @@ mod migrations {
 pub async fn run_migrations(client: &mut tokio_postgres::Client) -> Result<Report, Error> {
     migrations::migrations::runner().run_async(client).await
 }
+
+/// Creates the database connection pool. Connections are opened lazily on first use.
+pub fn create_pool(config: &DatabaseConfig, database_url: &str) -> Result<Pool, Box<dyn std::error::Error + Send + Sync>> {
+    let pg_config: tokio_postgres::Config = database_url.parse()?;
+    let recycling_method = if config.verify_connections {
+        RecyclingMethod::Verified
+    } else {
+        RecyclingMethod::Fast
+    };
+    let manager = Manager::from_config(pg_config, NoTls, ManagerConfig { recycling_method });
+    let pool = Pool::builder(manager)
+        .max_size(config.pool_size)
+        .wait_timeout(Some(Duration::from_secs(config.wait_timeout_seconds)))
+        .create_timeout(Some(Duration::from_secs(config.connect_timeout_seconds)))
+        .recycle_timeout(Some(Duration::from_secs(config.connect_timeout_seconds)))
+        .runtime(Runtime::Tokio1)
+        .build()?;
+    Ok(pool)
+}
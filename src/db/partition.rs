@@ -0,0 +1,90 @@
+//! Per-draw partitions of the wager tables. `draw_wager` and `win` are partitioned by the draw
+//! of the row, `board` and `selection` by the last draw of their wager, so that all rows of a
+//! draw are removed by dropping its partitions once the draw and its wagers are no longer needed.
+
+use tokio_postgres::{Client, Error, GenericClient};
+
+/// Partitioned tables, in the order their partitions are dropped: `selection` references
+/// `board`, so its partition goes first.
+pub const PARTITIONED_TABLES: [&str; 4] = ["win", "draw_wager", "selection", "board"];
+
+/// Name of the partition of `table` for a draw.
+pub fn partition_name(table: &str, draw_id: i32) -> String {
+    format!("{}_d{}", table, draw_id)
+}
+
+/// Creates the partitions of a draw in all partitioned tables, if they do not exist yet. Each
+/// partition is created as a table of its own and then attached, which does not block reads
+/// and writes of the other partitions. Must not run inside a transaction, which would hold
+/// the locks until it ends. Completes partitions left unattached by an earlier call.
+pub async fn create_draw_partitions(client: &Client, draw_id: i32) -> Result<(), Error> {
+    for table in PARTITIONED_TABLES {
+        let partition = partition_name(table, draw_id);
+        if attachment(client, table, &partition).await?.is_some() {
+            continue;
+        }
+        client
+            .batch_execute(&format!("CREATE TABLE IF NOT EXISTS {} (LIKE {} INCLUDING DEFAULTS)", partition, table))
+            .await?;
+        client
+            .batch_execute(&format!("ALTER TABLE {} ATTACH PARTITION {} FOR VALUES IN ({})", table, partition, draw_id))
+            .await?;
+    }
+    Ok(())
+}
+
+/// Whether all partitions of a draw are attached, so that wagers can be placed on the draw.
+pub async fn has_draw_partitions(client: &impl GenericClient, draw_id: i32) -> Result<bool, Error> {
+    let partitions: Vec<String> = PARTITIONED_TABLES.iter().map(|table| partition_name(table, draw_id)).collect();
+    let row = client
+        .query_one(
+            "SELECT COUNT(*) FROM pg_inherits i JOIN pg_class c ON c.oid = i.inhrelid
+             WHERE c.relname = ANY($1) AND NOT i.inhdetachpending",
+            &[&partitions],
+        )
+        .await?;
+    Ok(row.get::<_, i64>(0) as usize == PARTITIONED_TABLES.len())
+}
+
+/// Whether `partition` is attached to `table`, and if so whether a concurrent detach of it
+/// was interrupted.
+async fn attachment(client: &impl GenericClient, table: &str, partition: &str) -> Result<Option<bool>, Error> {
+    Ok(client
+        .query_opt(
+            "SELECT i.inhdetachpending FROM pg_inherits i JOIN pg_class c ON c.oid = i.inhrelid
+             WHERE c.relname = $1 AND i.inhparent = to_regclass($2)",
+            &[&partition, &table],
+        )
+        .await?
+        .map(|row| row.get(0)))
+}
+
+/// Detaches the partition of a draw from `table` without blocking reads and writes of the
+/// other partitions, and drops it. Must not run inside a transaction. A detach interrupted by
+/// an earlier run is finished first. Returns whether the partition existed.
+pub async fn detach_and_drop_partition(client: &Client, table: &str, draw_id: i32) -> Result<bool, Error> {
+    let partition = partition_name(table, draw_id);
+    match attachment(client, table, &partition).await? {
+        Some(true) => client.batch_execute(&format!("ALTER TABLE {} DETACH PARTITION {} FINALIZE", table, partition)).await?,
+        Some(false) => client.batch_execute(&format!("ALTER TABLE {} DETACH PARTITION {} CONCURRENTLY", table, partition)).await?,
+        None => {},
+    }
+    let existed = client
+        .query_opt("SELECT 1 FROM pg_class WHERE relname = $1 AND relkind = 'r'", &[&partition])
+        .await?
+        .is_some();
+    client.batch_execute(&format!("DROP TABLE IF EXISTS {}", partition)).await?;
+    Ok(existed)
+}
+
+/// Detaches and drops the partitions of a draw in all partitioned tables, like
+/// `detach_and_drop_partition`. Returns the number of partitions that existed.
+pub async fn drop_draw_partitions(client: &Client, draw_id: i32) -> Result<u64, Error> {
+    let mut dropped = 0;
+    for table in PARTITIONED_TABLES {
+        if detach_and_drop_partition(client, table, draw_id).await? {
+            dropped += 1;
+        }
+    }
+    Ok(dropped)
+}
@@ -0,0 +1,38 @@
+use tokio_postgres::{Error, GenericClient};
+use uuid::Uuid;
+use crate::core::refund::Refund;
+
+/// Fetches the ids and stakes of the next `limit` active wagers participating in a draw that
+/// have not been refunded for it, ordered by wager id. Pass `Uuid::nil()` as `after_wager_id`
+/// for the first batch and the id of the last returned wager for the following ones.
+pub async fn get_draw_wager_stakes_batch(client: &impl GenericClient, draw_id: i32, after_wager_id: Uuid, limit: i64) -> Result<Vec<(Uuid, u64)>, Error> {
+    let rows = client
+        .query(
+            "SELECT w.id, w.stake FROM draw_wager dw JOIN wager w ON w.id = dw.wager_id
+             WHERE dw.draw_id = $1 AND dw.wager_id > $2 AND w.status = 'Active'
+               AND NOT EXISTS (SELECT 1 FROM refund r WHERE r.wager_id = dw.wager_id AND r.draw_id = dw.draw_id)
+             ORDER BY dw.wager_id LIMIT $3",
+            &[&draw_id, &after_wager_id, &limit],
+        )
+        .await?;
+    Ok(rows.iter().map(|row| (row.get("id"), row.get::<_, i32>("stake") as u64)).collect())
+}
+
+/// Inserts a batch of refunds with a single statement.
+pub async fn insert_refunds(client: &impl GenericClient, refunds: &[Refund]) -> Result<u64, Error> {
+    if refunds.is_empty() {
+        return Ok(0);
+    }
+    let ids: Vec<Uuid> = refunds.iter().map(|r| r.id).collect();
+    let wager_ids: Vec<Uuid> = refunds.iter().map(|r| r.wager_id).collect();
+    let draw_ids: Vec<i32> = refunds.iter().map(|r| r.draw_id).collect();
+    let amounts: Vec<i64> = refunds.iter().map(|r| r.amount as i64).collect();
+    let created_at: Vec<_> = refunds.iter().map(|r| r.created_at).collect();
+    client
+        .execute(
+            "INSERT INTO refund (id, wager_id, draw_id, amount, created_at)
+             SELECT * FROM UNNEST($1::uuid[], $2::uuid[], $3::int4[], $4::int8[], $5::timestamptz[])",
+            &[&ids, &wager_ids, &draw_ids, &amounts, &created_at],
+        )
+        .await
+}
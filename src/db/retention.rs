@@ -0,0 +1,66 @@
+//! Deletion of old draws and their wagers. Rows of the partitioned wager tables go with the
+//! partitions of their draw; the other functions delete at most one batch of rows, so callers
+//! can commit between batches and keep locks on the wager tables short.
+
+use chrono::{DateTime, Utc};
+use tokio_postgres::{Client, Error, Transaction};
+
+/// Fetches the ids of finalized and cancelled draws drawn before `before`, oldest first.
+/// Cancelled draws without a draw time are aged by their close time. A draw is left for a
+/// later run while an earlier draw of its game is still in progress, as wagers in both draws
+/// keep their boards in the partitions of the later one.
+pub async fn get_expired_draws(client: &Client, before: DateTime<Utc>, limit: i64) -> Result<Vec<i32>, Error> {
+    let rows = client
+        .query(
+            "SELECT d.id FROM draw d
+             WHERE d.status IN ('Finalized', 'Cancelled') AND COALESCE(d.draw_time, d.close_time) < $1
+               AND NOT EXISTS (
+                   SELECT 1 FROM draw e
+                   WHERE e.game_id = d.game_id AND e.status NOT IN ('Finalized', 'Cancelled')
+                     AND COALESCE(e.draw_time, e.close_time) < COALESCE(d.draw_time, d.close_time)
+               )
+             ORDER BY COALESCE(d.draw_time, d.close_time), d.id LIMIT $2",
+            &[&before, &limit],
+        )
+        .await?;
+    Ok(rows.iter().map(|row| row.get("id")).collect())
+}
+
+/// Deletes a batch of the refunds of a draw.
+pub async fn delete_draw_refunds_batch(client: &Client, draw_id: i32, limit: i64) -> Result<u64, Error> {
+    client
+        .execute(
+            "DELETE FROM refund WHERE id IN (SELECT id FROM refund WHERE draw_id = $1 LIMIT $2)",
+            &[&draw_id, &limit],
+        )
+        .await
+}
+
+/// Deletes a batch of the remaining refunds of the wagers whose last draw is `draw_id`.
+pub async fn delete_wager_refunds_batch(client: &Client, last_draw_id: i32, limit: i64) -> Result<u64, Error> {
+    client
+        .execute(
+            "DELETE FROM refund WHERE id IN (
+                 SELECT r.id FROM refund r JOIN wager w ON w.id = r.wager_id WHERE w.last_draw_id = $1 LIMIT $2
+             )",
+            &[&last_draw_id, &limit],
+        )
+        .await
+}
+
+/// Deletes a batch of the wagers whose last draw is `draw_id`. Their draws, boards,
+/// selections and wins must already be gone with the partitions of their draws.
+pub async fn delete_wagers_batch(client: &Client, last_draw_id: i32, limit: i64) -> Result<u64, Error> {
+    client
+        .execute(
+            "DELETE FROM wager WHERE id IN (SELECT id FROM wager WHERE last_draw_id = $1 LIMIT $2)",
+            &[&last_draw_id, &limit],
+        )
+        .await
+}
+
+/// Deletes a draw once its partitions, refunds and wagers are gone, with its external win totals.
+pub async fn delete_draw(transaction: &Transaction<'_>, draw_id: i32) -> Result<u64, Error> {
+    transaction.execute("DELETE FROM external_win_total WHERE draw_id = $1", &[&draw_id]).await?;
+    transaction.execute("DELETE FROM draw WHERE id = $1", &[&draw_id]).await
+}
@@ -0,0 +1,47 @@
+use tokio_postgres::{Error, GenericClient, Transaction};
+use uuid::Uuid;
+use tracing::{info, error};
+use crate::config::app_config::ScheduleExceptionConfig;
+
+/// Locks the draw schedule of a game until the end of the transaction. Draw planning and
+/// schedule changes take this lock, so draws are never planned from outdated exceptions.
+pub async fn lock_game_schedule(transaction: &Transaction<'_>, game_id: Uuid) -> Result<(), Error> {
+    transaction
+        .execute("SELECT id FROM game WHERE id = $1 FOR UPDATE", &[&game_id])
+        .await?;
+    Ok(())
+}
+
+pub async fn insert_schedule_exception(client: &impl GenericClient, game_id: Uuid, exception: &ScheduleExceptionConfig) -> Result<(), Error> {
+    info!("Attempting to insert schedule exception for game_id {}: {:?}", game_id, exception);
+    // JSON is passed as text and converted by Postgres; serializing the config cannot fail
+    let data = serde_json::to_string(exception).expect("Schedule exception serializes to JSON");
+    client
+        .execute(
+            "INSERT INTO schedule_exception (id, game_id, exception) VALUES ($1, $2, $3::TEXT::JSONB)",
+            &[&Uuid::now_v7(), &game_id, &data],
+        )
+        .await?;
+    info!("Successfully inserted schedule exception for game_id {}", game_id);
+    Ok(())
+}
+
+/// Fetches the runtime schedule exceptions of a game in the order they were added.
+pub async fn get_schedule_exceptions(client: &impl GenericClient, game_id: Uuid) -> Result<Vec<ScheduleExceptionConfig>, Error> {
+    let rows = client
+        .query(
+            "SELECT id, exception::TEXT AS exception FROM schedule_exception WHERE game_id = $1 ORDER BY id",
+            &[&game_id],
+        )
+        .await?;
+    Ok(rows
+        .iter()
+        .filter_map(|row| match serde_json::from_str(row.get("exception")) {
+            Ok(exception) => Some(exception),
+            Err(e) => {
+                error!("Invalid schedule exception {} in database: {}", row.get::<_, Uuid>("id"), e);
+                None
+            }
+        })
+        .collect())
+}
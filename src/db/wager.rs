@@ -1,68 +1,293 @@
-use tokio_postgres::{Client, Error};
+use std::fmt;
+use tokio_postgres::error::SqlState;
+use tokio_postgres::{Client, Error, Row, Transaction};
 use uuid::Uuid;
-use tracing::{info};
-use crate::core::wager::{Wager};
-use crate::core::board::Board;
+use tracing::{info, error};
+use crate::core::wager::{Wager, WagerStatus};
+use crate::core::board::{Board, GameType};
 use crate::core::selection::Selection;
+use crate::db;
 
-pub async fn insert_wager(client: &Client, wager: &Wager, draws: Vec<i32>) -> Result<(), Error> {
+/// Failure to persist a wager.
+#[derive(Debug)]
+pub enum InsertWagerError {
+    /// Some of the requested draws are no longer open for wagering.
+    DrawsNotOpen(Vec<i32>),
+    /// The player already placed a wager with the same idempotency key.
+    DuplicateIdempotencyKey,
+    /// The stake or price of the wager or a board does not fit its database column.
+    AmountTooLarge(u32),
+    Database(Error),
+}
+
+impl fmt::Display for InsertWagerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InsertWagerError::DrawsNotOpen(draws) => write!(f, "Draws {:?} are not open", draws),
+            InsertWagerError::DuplicateIdempotencyKey => write!(f, "Idempotency key is already used"),
+            InsertWagerError::AmountTooLarge(amount) => write!(f, "Amount {} is too large", amount),
+            InsertWagerError::Database(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for InsertWagerError {}
+
+impl From<Error> for InsertWagerError {
+    fn from(e: Error) -> Self {
+        if e.code() == Some(&SqlState::UNIQUE_VIOLATION)
+            && e.as_db_error().and_then(|db_error| db_error.constraint()) == Some(IDEMPOTENCY_KEY_INDEX)
+        {
+            return InsertWagerError::DuplicateIdempotencyKey;
+        }
+        InsertWagerError::Database(e)
+    }
+}
+
+/// Unique index on the idempotency keys of the wagers of a player.
+const IDEMPOTENCY_KEY_INDEX: &str = "idx_wager_user_idempotency_key";
+
+/// Client supplied key identifying a place wager request, with the encoded request. A retried
+/// request carries the same key and request; only the SHA-256 of the request is stored.
+#[derive(Debug, Clone)]
+pub struct IdempotencyKey {
+    pub key: String,
+    pub request: Vec<u8>,
+}
+
+/// Formats values as a Postgres array literal, for passing arrays of arrays through `UNNEST`.
+fn array_literal<T: ToString>(values: &[T]) -> String {
+    format!("{{{}}}", values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(","))
+}
+
+/// Inserts a wager with its draws, boards and selections within the given transaction, using
+/// a constant number of statements regardless of the number of draws and boards. The draws
+/// are locked and checked to still be open, so a draw cannot close before the transaction
+/// commits. A wager placed with an idempotency key the player has already used fails with
+/// `DuplicateIdempotencyKey`. The boards and selections go to the partitions of the last draw
+/// of the wager, which must be in `wager.draws`.
+pub async fn insert_wager(
+    transaction: &Transaction<'_>,
+    wager: &Wager,
+    draws: Vec<i32>,
+    idempotency_key: Option<&IdempotencyKey>,
+) -> Result<(), InsertWagerError> {
     info!("Attempting to insert wager: {:?} to draws {:?}", wager, draws);
-    let stake = wager.stake as i32;
-    let price = wager.price as i32;
-    client
-        .execute(
-            "INSERT INTO wager (id, user_id, stake, price, created_at) VALUES ($1, $2, $3, $4, $5)",
-            &[&wager.id, &wager.user_id, &stake, &price, &wager.created_at],
+
+    let open_rows = transaction
+        .query(
+            "SELECT id FROM draw WHERE id = ANY($1) AND status = 'Open' AND close_time > NOW() FOR SHARE",
+            &[&draws],
         )
         .await?;
-    for draw_id in &draws {
-        client
-            .execute(
-                "INSERT INTO draw_wager (draw_id ,wager_id) VALUES ($1, $2)",
-                &[&draw_id, &wager.id],
-            )
-            .await?;
-    }
-    for board in &wager.boards{
-      client
-          .execute(
-              "INSERT INTO board (id, wager_id, game_type) VALUES ($1, $2, $3)",
-              &[&board.id, &board.wager_id, &board.game_type.to_string()],
-          )
-          .await?;
-      for selection in &board.selections {
-          client
-              .execute(
-                 "INSERT INTO selection (id, board_id, name, values) VALUES ($1, $2, $3, $4)",
-                  &[&selection.id, &board.id, &selection.name, &selection.values],
-          )
-          .await?;
-      }
+    let open_draws: Vec<i32> = open_rows.iter().map(|row| row.get("id")).collect();
+    let not_open: Vec<i32> = draws.iter().copied().filter(|d| !open_draws.contains(d)).collect();
+    if !not_open.is_empty() {
+        return Err(InsertWagerError::DrawsNotOpen(not_open));
     }
+    let Some(last_draw_id) = wager.last_draw_id() else {
+        return Err(InsertWagerError::DrawsNotOpen(draws));
+    };
+
+    let amount = |amount: u32| i32::try_from(amount).map_err(|_| InsertWagerError::AmountTooLarge(amount));
+    let stake = amount(wager.stake)?;
+    let price = amount(wager.price)?;
+    let board_stakes: Vec<i32> = wager.boards.iter().map(|b| amount(b.stake)).collect::<Result<_, _>>()?;
+    transaction
+        .execute(
+            "INSERT INTO wager (id, user_id, stake, price, status, created_at, last_draw_id, idempotency_key, request_hash)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, sha256($9))",
+            &[
+                &wager.id,
+                &wager.user_id,
+                &stake,
+                &price,
+                &wager.status.to_string(),
+                &wager.created_at,
+                &last_draw_id,
+                &idempotency_key.map(|k| k.key.as_str()),
+                &idempotency_key.map(|k| k.request.as_slice()),
+            ],
+        )
+        .await?;
+    transaction
+        .execute(
+            "INSERT INTO draw_wager (draw_id, wager_id) SELECT draw_id, $2 FROM UNNEST($1::int4[]) AS draw_id",
+            &[&draws, &wager.id],
+        )
+        .await?;
+
+    let board_ids: Vec<Uuid> = wager.boards.iter().map(|b| b.id).collect();
+    let game_types: Vec<String> = wager.boards.iter().map(|b| b.game_type.to_string()).collect();
+    transaction
+        .execute(
+            "INSERT INTO board (id, wager_id, last_draw_id, game_type, stake)
+             SELECT id, $2, $5, game_type, stake FROM UNNEST($1::uuid[], $3::text[], $4::int4[]) AS t(id, game_type, stake)",
+            &[&board_ids, &wager.id, &game_types, &board_stakes, &last_draw_id],
+        )
+        .await?;
+
+    let selections: Vec<(Uuid, &Selection)> = wager
+        .boards
+        .iter()
+        .flat_map(|b| b.selections.iter().map(move |s| (b.id, s)))
+        .collect();
+    let selection_ids: Vec<Uuid> = selections.iter().map(|(_, s)| s.id).collect();
+    let selection_board_ids: Vec<Uuid> = selections.iter().map(|(board_id, _)| *board_id).collect();
+    let names: Vec<&str> = selections.iter().map(|(_, s)| s.name.as_str()).collect();
+    let values: Vec<String> = selections.iter().map(|(_, s)| array_literal(&s.values)).collect();
+    let machine_picked: Vec<String> = selections.iter().map(|(_, s)| array_literal(&s.machine_picked)).collect();
+    transaction
+        .execute(
+            "INSERT INTO selection (id, board_id, last_draw_id, name, values, machine_picked)
+             SELECT id, board_id, $6, name, values::int4[], machine_picked::bool[]
+             FROM UNNEST($1::uuid[], $2::uuid[], $3::text[], $4::text[], $5::text[]) AS t(id, board_id, name, values, machine_picked)",
+            &[&selection_ids, &selection_board_ids, &names, &values, &machine_picked, &last_draw_id],
+        )
+        .await?;
+
     info!("Successfully inserted wager: {:?} to draws {:?}", wager, draws);
     Ok(())
 }
 
-pub async fn insert_board(client: &Client, board: &Board) -> Result<(), Error> {
-    info!("Attempting to insert board: {:?}", board);
-    client
-        .execute(
-            "INSERT INTO board (id, wager_id, game_type) VALUES ($1, $2, $3)",
-            &[&board.id, &board.wager_id, &board.game_type.to_string()],
+/// Columns of a wager joined with its boards and selections, as read by `wagers_from_rows`.
+const WAGER_BOARD_COLUMNS: &str = "w.id AS wager_id, w.user_id, w.stake, w.price, w.status, w.created_at,
+    b.id AS board_id, b.game_type, b.stake AS board_stake, s.id AS selection_id, s.name, s.values, s.machine_picked";
+
+/// Assembles wagers with their boards and selections from rows of `WAGER_BOARD_COLUMNS`
+/// ordered by wager, board and selection. The draws of the wagers are not loaded.
+fn wagers_from_rows(rows: Vec<Row>) -> Vec<Wager> {
+    let mut wagers: Vec<Wager> = Vec::new();
+    for row in rows {
+        let wager_id: Uuid = row.get("wager_id");
+        if wagers.last().map(|w| w.id) != Some(wager_id) {
+            let status_str: String = row.get("status");
+            let Ok(status) = status_str.parse::<WagerStatus>() else {
+                error!("Unknown wager status in database: '{}'", status_str);
+                continue;
+            };
+            wagers.push(Wager {
+                id: wager_id,
+                user_id: row.get("user_id"),
+                draws: Vec::new(),
+                boards: Vec::new(),
+                stake: row.get::<_, i32>("stake") as u32,
+                price: row.get::<_, i32>("price") as u32,
+                status,
+                created_at: row.get("created_at"),
+            });
+        }
+        let Some(wager) = wagers.last_mut() else {
+            continue;
+        };
+        let Some(board_id) = row.get::<_, Option<Uuid>>("board_id") else {
+            continue;
+        };
+        if wager.boards.last().map(|b| b.id) != Some(board_id) {
+            let game_type_str: String = row.get("game_type");
+            let game_type = match game_type_str.parse::<GameType>() {
+                Ok(game_type) => game_type,
+                Err(_) => {
+                    error!("Unknown game type in database: '{}'", game_type_str);
+                    continue;
+                }
+            };
+            wager.boards.push(Board {
+                id: board_id,
+                wager_id,
+                game_type,
+                selections: Vec::new(),
+                stake: row.get::<_, i32>("board_stake") as u32,
+            });
+        }
+        if let (Some(board), Some(selection_id)) = (wager.boards.last_mut(), row.get::<_, Option<Uuid>>("selection_id"))
+            && board.id == board_id
+        {
+            board.selections.push(Selection {
+                id: selection_id,
+                name: row.get("name"),
+                values: row.get("values"),
+                machine_picked: row.get("machine_picked"),
+            });
+        }
+    }
+    wagers
+}
+
+/// Fetches a wager with its boards, selections and draws, or `None` if it does not exist.
+pub async fn get_wager(client: &Client, wager_id: Uuid) -> Result<Option<Wager>, Error> {
+    let rows = client
+        .query(
+            &format!(
+                "SELECT {}
+                 FROM wager w
+                 LEFT JOIN board b ON b.last_draw_id = w.last_draw_id AND b.wager_id = w.id
+                 LEFT JOIN selection s ON s.last_draw_id = b.last_draw_id AND s.board_id = b.id
+                 WHERE w.id = $1
+                 ORDER BY b.id, s.name",
+                WAGER_BOARD_COLUMNS
+            ),
+            &[&wager_id],
         )
         .await?;
-    info!("Successfully inserted board: {:?}", board);
-    Ok(())
+    let Some(mut wager) = wagers_from_rows(rows).pop() else {
+        return Ok(None);
+    };
+    wager.draws = db::draw::get_wager_draws(client, wager_id).await?;
+    Ok(Some(wager))
 }
 
-pub async fn insert_selection(client: &Client, selection: &Selection, board_id: Uuid) -> Result<(), Error> {
-    info!("Attempting to insert selection: {:?}", selection);
-    client
-        .execute(
-            "INSERT INTO selection (id, board_id, name, values) VALUES ($1, $2, $3, $4)",
-            &[&selection.id, &board_id, &selection.name, &selection.values],
+/// Finds the wager a player placed with an idempotency key. Returns its id and whether it was
+/// placed with the same request, or `None` if the key has not been used.
+pub async fn find_idempotent_wager(client: &Client, user_id: Uuid, idempotency_key: &IdempotencyKey) -> Result<Option<(Uuid, bool)>, Error> {
+    let row = client
+        .query_opt(
+            "SELECT id, request_hash = sha256($3) AS same_request FROM wager WHERE user_id = $1 AND idempotency_key = $2",
+            &[&user_id, &idempotency_key.key, &idempotency_key.request.as_slice()],
         )
         .await?;
-    info!("Successfully inserted selection: {:?}", selection);
+    Ok(row.map(|row| (row.get("id"), row.get::<_, Option<bool>>("same_request").unwrap_or(false))))
+}
+
+/// Locks a wager against concurrent cancellation until the transaction ends and returns its
+/// current status, or `None` if it does not exist.
+pub async fn lock_wager_status(transaction: &Transaction<'_>, wager_id: Uuid) -> Result<Option<WagerStatus>, Error> {
+    let row = transaction
+        .query_opt("SELECT status FROM wager WHERE id = $1 FOR UPDATE", &[&wager_id])
+        .await?;
+    Ok(row.and_then(|row| row.get::<_, String>("status").parse::<WagerStatus>().ok()))
+}
+
+pub async fn update_wager_status(transaction: &Transaction<'_>, wager_id: Uuid, status: &WagerStatus) -> Result<(), Error> {
+    transaction
+        .execute("UPDATE wager SET status = $1 WHERE id = $2", &[&status.to_string(), &wager_id])
+        .await?;
     Ok(())
 }
+
+/// Fetches the next `limit` active wagers participating in a draw with their boards, ordered by wager id.
+/// Pass `Uuid::nil()` as `after_wager_id` for the first batch and the id of the last returned
+/// wager for the following ones; an empty result means there are no more wagers in the draw.
+/// The draws of the returned wagers are not loaded.
+pub async fn get_draw_wagers_batch(client: &Client, draw_id: i32, after_wager_id: Uuid, limit: i64) -> Result<Vec<Wager>, Error> {
+    let rows = client
+        .query(
+            &format!(
+                "WITH batch AS (
+                     SELECT dw.wager_id FROM draw_wager dw JOIN wager w ON w.id = dw.wager_id
+                     WHERE dw.draw_id = $1 AND dw.wager_id > $2 AND w.status = 'Active'
+                     ORDER BY dw.wager_id LIMIT $3
+                 )
+                 SELECT {}
+                 FROM batch
+                 JOIN wager w ON w.id = batch.wager_id
+                 LEFT JOIN board b ON b.last_draw_id = w.last_draw_id AND b.wager_id = w.id
+                 LEFT JOIN selection s ON s.last_draw_id = b.last_draw_id AND s.board_id = b.id
+                 ORDER BY w.id, b.id, s.name",
+                WAGER_BOARD_COLUMNS
+            ),
+            &[&draw_id, &after_wager_id, &limit],
+        )
+        .await?;
+    Ok(wagers_from_rows(rows))
+}
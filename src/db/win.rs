@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use tokio_postgres::{Client, Error, GenericClient, Transaction};
+use uuid::Uuid;
+use tracing::info;
+use crate::core::win::Win;
+
+/// Removes the winset of a draw so that it can be calculated again from scratch.
+pub async fn delete_wins_for_draw(client: &impl GenericClient, draw_id: i32) -> Result<u64, Error> {
+    info!("Attempting to delete wins for draw {}", draw_id);
+    let deleted = client
+        .execute("DELETE FROM win WHERE draw_id = $1", &[&draw_id])
+        .await?;
+    info!("Deleted {} wins for draw {}", deleted, draw_id);
+    Ok(deleted)
+}
+
+/// Inserts a batch of wins with a single statement.
+pub async fn insert_wins(client: &impl GenericClient, wins: &[Win]) -> Result<u64, Error> {
+    if wins.is_empty() {
+        return Ok(0);
+    }
+    let ids: Vec<Uuid> = wins.iter().map(|w| w.id).collect();
+    let wager_ids: Vec<Uuid> = wins.iter().map(|w| w.wager_id).collect();
+    let draw_ids: Vec<i32> = wins.iter().map(|w| w.draw_id).collect();
+    let board_ids: Vec<Uuid> = wins.iter().map(|w| w.board_id).collect();
+    let win_class_ids: Vec<Uuid> = wins.iter().map(|w| w.win_class_id).collect();
+    let winning_rows: Vec<i32> = wins.iter().map(|w| w.winning_rows as i32).collect();
+    let row_stakes: Vec<i64> = wins.iter().map(|w| w.row_stake as i64).collect();
+    client
+        .execute(
+            "INSERT INTO win (id, wager_id, draw_id, board_id, win_class_id, winning_rows, row_stake)
+             SELECT * FROM UNNEST($1::uuid[], $2::uuid[], $3::int4[], $4::uuid[], $5::uuid[], $6::int4[], $7::int8[])",
+            &[&ids, &wager_ids, &draw_ids, &board_ids, &win_class_ids, &winning_rows, &row_stakes],
+        )
+        .await
+}
+
+/// Fetches the wins of a wager in all of its draws, ordered by draw.
+pub async fn get_wager_wins(client: &Client, wager_id: Uuid) -> Result<Vec<Win>, Error> {
+    let rows = client
+        .query(
+            "SELECT id, wager_id, draw_id, board_id, win_class_id, winning_rows, row_stake, amount
+             FROM win WHERE wager_id = $1 ORDER BY draw_id, board_id, win_class_id",
+            &[&wager_id],
+        )
+        .await?;
+    Ok(rows
+        .iter()
+        .map(|row| Win {
+            id: row.get("id"),
+            wager_id: row.get("wager_id"),
+            draw_id: row.get("draw_id"),
+            board_id: row.get("board_id"),
+            win_class_id: row.get("win_class_id"),
+            winning_rows: row.get::<_, i32>("winning_rows") as u32,
+            row_stake: row.get::<_, i64>("row_stake") as u64,
+            amount: row.get::<_, i64>("amount") as u64,
+        })
+        .collect())
+}
+
+/// Winning rows and their combined stake in one win class of a draw.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WinClassWinners {
+    pub win_class_id: Uuid,
+    pub winning_rows: u64,
+    pub winning_stake: u64,
+}
+
+pub async fn get_win_class_winners(client: &impl GenericClient, draw_id: i32) -> Result<Vec<WinClassWinners>, Error> {
+    let rows = client
+        .query(
+            "SELECT win_class_id, SUM(winning_rows)::BIGINT AS winning_rows, SUM(winning_rows * row_stake)::BIGINT AS winning_stake
+             FROM win WHERE draw_id = $1 GROUP BY win_class_id",
+            &[&draw_id],
+        )
+        .await?;
+    Ok(rows
+        .iter()
+        .map(|row| WinClassWinners {
+            win_class_id: row.get("win_class_id"),
+            winning_rows: row.get::<_, i64>("winning_rows") as u64,
+            winning_stake: row.get::<_, i64>("winning_stake") as u64,
+        })
+        .collect())
+}
+
+/// Sets the amount of every win of a class to the same amount per winning row. Returns the
+/// total amount of the wins.
+pub async fn update_win_amounts_per_row(transaction: &Transaction<'_>, draw_id: i32, win_class_id: Uuid, amount_per_row: u64) -> Result<u64, Error> {
+    let row = transaction
+        .query_one(
+            "WITH updated AS (
+                 UPDATE win SET amount = winning_rows * $3::BIGINT WHERE draw_id = $1 AND win_class_id = $2 RETURNING amount
+             )
+             SELECT COALESCE(SUM(amount), 0)::BIGINT AS total FROM updated",
+            &[&draw_id, &win_class_id, &(amount_per_row as i64)],
+        )
+        .await?;
+    Ok(row.get::<_, i64>("total") as u64)
+}
+
+/// Shares `total` between the wins of a class in proportion to their stake, rounding each win
+/// down. Returns the total amount of the wins, which is less than `total` by the rounding.
+pub async fn update_win_amounts_by_stake(transaction: &Transaction<'_>, draw_id: i32, win_class_id: Uuid, total: u64, winning_stake: u64) -> Result<u64, Error> {
+    let row = transaction
+        .query_one(
+            "WITH updated AS (
+                 UPDATE win SET amount = FLOOR(winning_rows::NUMERIC * row_stake * $3::BIGINT / $4::BIGINT)::BIGINT
+                 WHERE draw_id = $1 AND win_class_id = $2 RETURNING amount
+             )
+             SELECT COALESCE(SUM(amount), 0)::BIGINT AS total FROM updated",
+            &[&draw_id, &win_class_id, &(total as i64), &(winning_stake as i64)],
+        )
+        .await?;
+    Ok(row.get::<_, i64>("total") as u64)
+}
+
+/// Draw turnover, i.e. the combined stake of all active wagers participating in the draw.
+pub async fn get_draw_turnover(client: &impl GenericClient, draw_id: i32) -> Result<u64, Error> {
+    let row = client
+        .query_one(
+            "SELECT COALESCE(SUM(w.stake), 0)::BIGINT AS turnover FROM draw_wager dw JOIN wager w ON w.id = dw.wager_id
+             WHERE dw.draw_id = $1 AND w.status = 'Active'",
+            &[&draw_id],
+        )
+        .await?;
+    Ok(row.get::<_, i64>("turnover") as u64)
+}
+
+pub async fn get_external_win_totals(client: &impl GenericClient, draw_id: i32) -> Result<HashMap<Uuid, u64>, Error> {
+    let rows = client
+        .query("SELECT win_class_id, amount FROM external_win_total WHERE draw_id = $1", &[&draw_id])
+        .await?;
+    Ok(rows
+        .iter()
+        .map(|row| (row.get("win_class_id"), row.get::<_, i64>("amount") as u64))
+        .collect())
+}
+
+/// Sets the total win sum of an external win class in a draw.
+pub async fn set_external_win_total(client: &impl GenericClient, draw_id: i32, win_class_id: Uuid, amount: u64) -> Result<(), Error> {
+    info!("Setting external win total of class {} in draw {} to {}", win_class_id, draw_id, amount);
+    client
+        .execute(
+            "INSERT INTO external_win_total (draw_id, win_class_id, amount) VALUES ($1, $2, $3)
+             ON CONFLICT (draw_id, win_class_id) DO UPDATE SET amount = $3",
+            &[&draw_id, &win_class_id, &(amount as i64)],
+        )
+        .await?;
+    Ok(())
+}
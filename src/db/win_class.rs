@@ -0,0 +1,94 @@
+use tokio_postgres::{Client, Error};
+use uuid::Uuid;
+use tracing::{info, error};
+use crate::core::win_class::{RequiredHits, WinClass, WinClassType};
+
+pub async fn get_win_classes(client: &Client, game_id: Uuid) -> Result<Vec<WinClass>, Error> {
+    info!("Attempting to get win classes for game_id: {}", game_id);
+    let rows = client
+        .query(
+            "SELECT id, game_id, name, winclass_type, factor, constant, percentage, min_cap, max_cap, rank, draw_levels, hits FROM win_class WHERE game_id = $1 AND active ORDER BY rank, name",
+            &[&game_id],
+        )
+        .await?;
+
+    let mut win_classes = Vec::new();
+    for row in rows {
+        let type_str: String = row.get("winclass_type");
+        let win_class_type = match type_str.parse::<WinClassType>() {
+            Ok(win_class_type) => win_class_type,
+            Err(_) => {
+                error!("Unknown win class type in database: '{}'", type_str);
+                continue;
+            }
+        };
+        let draw_levels: Vec<String> = row.get("draw_levels");
+        let hits: Vec<i32> = row.get("hits");
+
+        win_classes.push(WinClass {
+            id: row.get("id"),
+            game_id: row.get("game_id"),
+            name: row.get("name"),
+            r#type: win_class_type,
+            factor: row.get::<_, Option<i32>>("factor").map(|f| f as u32),
+            constant: row.get::<_, Option<i64>>("constant").map(|c| c as u64),
+            percentage: row.get::<_, Option<i32>>("percentage").map(|p| p as u32),
+            min_cap: row.get::<_, Option<i64>>("min_cap").map(|c| c as u64),
+            max_cap: row.get::<_, Option<i64>>("max_cap").map(|c| c as u64),
+            rank: row.get("rank"),
+            required_hits: draw_levels
+                .into_iter()
+                .zip(hits)
+                .map(|(draw_level, hits)| RequiredHits { draw_level, hits: hits as u32 })
+                .collect(),
+        });
+    }
+    info!("Found {} win classes for game_id: {}", win_classes.len(), game_id);
+    Ok(win_classes)
+}
+
+/// Upserts the configured win classes of a game and deactivates the ones no longer configured.
+pub async fn upsert_win_classes(client: &Client, game_id: Uuid, win_classes: &[WinClass]) -> Result<(), Error> {
+    let upsert_win_class_query = "
+        INSERT INTO win_class (id, game_id, name, winclass_type, factor, constant, percentage, min_cap, max_cap, rank, draw_levels, hits, active)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, TRUE)
+        ON CONFLICT (id) DO UPDATE SET game_id = $2, name = $3, winclass_type = $4, factor = $5, constant = $6, percentage = $7,
+            min_cap = $8, max_cap = $9, rank = $10, draw_levels = $11, hits = $12, active = TRUE
+    ";
+    for win_class in win_classes {
+        let draw_levels: Vec<&str> = win_class.required_hits.iter().map(|r| r.draw_level.as_str()).collect();
+        let hits: Vec<i32> = win_class.required_hits.iter().map(|r| r.hits as i32).collect();
+        client
+            .execute(
+                upsert_win_class_query,
+                &[
+                    &win_class.id,
+                    &win_class.game_id,
+                    &win_class.name,
+                    &win_class.r#type.to_string(),
+                    &win_class.factor.map(|f| f as i32),
+                    &win_class.constant.map(|c| c as i64),
+                    &win_class.percentage.map(|p| p as i32),
+                    &win_class.min_cap.map(|c| c as i64),
+                    &win_class.max_cap.map(|c| c as i64),
+                    &win_class.rank,
+                    &draw_levels,
+                    &hits,
+                ],
+            )
+            .await?;
+        info!("Upserted win class: {}", win_class.name);
+    }
+
+    let ids: Vec<Uuid> = win_classes.iter().map(|w| w.id).collect();
+    let deactivated = client
+        .execute(
+            "UPDATE win_class SET active = FALSE WHERE game_id = $1 AND active AND NOT (id = ANY($2))",
+            &[&game_id, &ids],
+        )
+        .await?;
+    if deactivated > 0 {
+        info!("Deactivated {} win classes no longer in configuration", deactivated);
+    }
+    Ok(())
+}
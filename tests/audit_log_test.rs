@@ -0,0 +1,28 @@
+use chrono::Utc;
+use rlottery::core::audit_log::AuditLog;
+use rlottery::core::draw::{Draw, DrawStatus};
+use uuid::Uuid;
+
+#[test]
+fn test_audit_log_snapshots_entity() {
+    let draw = Draw {
+        id: 42,
+        game_id: Uuid::new_v4(),
+        status: DrawStatus::Closed,
+        created_at: Utc::now(),
+        modified_at: Utc::now(),
+        open_time: Utc::now(),
+        close_time: Utc::now(),
+        draw_time: None,
+        winset_calculated_at: None,
+        winset_confirmed_at: None,
+        winning_numbers: Vec::new(),
+    };
+
+    let audit_log = AuditLog::new("draw", draw.id.to_string(), "DrawClosed", &draw).unwrap();
+
+    assert_eq!(audit_log.id.get_version_num(), 7);
+    assert_eq!(audit_log.entity_id, "42");
+    assert_eq!(audit_log.data["status"], "Closed");
+    assert_eq!(serde_json::from_value::<Draw>(audit_log.data).unwrap(), draw);
+}
@@ -0,0 +1,99 @@
+use rlottery::config::app_config::Config;
+use uuid::Uuid;
+
+const LOTTO_ID: &str = "a1b2c3d4-e5f6-7890-1234-567890abcdef";
+const KENO_ID: &str = "0b6c3f4e-8a1d-4c2b-9e7f-5d3a2b1c0e9f";
+
+fn game_toml(header: &str, id: &str, name: &str) -> String {
+    format!(
+        r#"
+{header}
+id = "{id}"
+lottery_operator_id = 1
+name = "{name}"
+open_draws = 4
+allowed_participations = [1, 2, 3]
+closed_state_duration_seconds = 300
+wager_classes = [{{ name = "normal", selections = ["primary"], number_of_selections = [6], stake_min = 100, stake_max = 100, stake_increment = 100 }}]
+draw_levels = [{{ name = "primary", selections = 6, min_value = 1, max_value = 40 }}]
+schedule = {{ daily = {{ time = "21:50" }} }}
+"#
+    )
+}
+
+fn load(name: &str, games: &str) -> Result<Config, config::ConfigError> {
+    let path = std::env::temp_dir().join(format!("rlottery_{}_{}.toml", name, std::process::id()));
+    std::fs::write(&path, format!("[lottery_operator]\nid = 1\nname = \"Operator\"\n{}", games)).unwrap();
+    let config = Config::from_file(path.to_str().unwrap());
+    std::fs::remove_file(&path).unwrap();
+    config
+}
+
+#[test]
+fn test_single_and_multiple_game_formats() {
+    let single = load("single", &game_toml("[game]", LOTTO_ID, "Lotto")).unwrap();
+    assert_eq!(single.games.len(), 1);
+    assert_eq!(single.resolve_game(None).unwrap().name, "Lotto");
+
+    let multiple = load("multiple", &(game_toml("[[games]]", LOTTO_ID, "Lotto") + &game_toml("[[games]]", KENO_ID, "Keno"))).unwrap();
+    let names: Vec<&str> = multiple.games.iter().map(|g| g.name.as_str()).collect();
+    assert_eq!(names, vec!["Lotto", "Keno"]);
+    assert_eq!(multiple.resolve_game(Some(Uuid::parse_str(KENO_ID).unwrap())).unwrap().name, "Keno");
+    assert!(multiple.resolve_game(None).is_err());
+    assert!(multiple.resolve_game(Some(Uuid::new_v4())).is_err());
+
+    assert!(load("duplicate", &(game_toml("[[games]]", LOTTO_ID, "Lotto") + &game_toml("[[games]]", LOTTO_ID, "Lotto 2"))).is_err());
+    assert!(load("empty", "").is_err());
+}
+
+#[test]
+fn test_retention_defaults_and_overrides() {
+    let game = game_toml("[game]", LOTTO_ID, "Lotto");
+    let defaults = load("retention_defaults", &game).unwrap();
+    assert_eq!(defaults.retention.max_age_days, 30);
+    assert!(defaults.retention.batch_size > 0);
+
+    let configured = load("retention", &format!("[retention]\nschedule = \"0 0 4 * * *\"\nmax_age_days = 7\n{}", game)).unwrap();
+    assert_eq!(configured.retention.schedule, "0 0 4 * * *");
+    assert_eq!(configured.retention.max_age_days, 7);
+    assert_eq!(configured.retention.batch_size, defaults.retention.batch_size);
+
+    assert!(load("retention_batch", &format!("[retention]\nbatch_size = 0\n{}", game)).is_err());
+}
+
+#[test]
+fn test_draw_levels_are_validated() {
+    let game = game_toml("[game]", LOTTO_ID, "Lotto");
+    let with_levels = |levels: &str| game.replace(r#"draw_levels = [{ name = "primary", selections = 6, min_value = 1, max_value = 40 }]"#, levels);
+    let levels = |secondary: &str| {
+        with_levels(&format!(r#"draw_levels = [{{ name = "primary", selections = 6, min_value = 1, max_value = 10 }}, {}]"#, secondary))
+    };
+
+    assert!(load("levels", &levels(r#"{ name = "secondary", selections = 4, dependent_on = "primary", min_value = 1, max_value = 10 }"#)).is_ok());
+    // The parent's numbers leave only 4 numbers of the same range
+    assert!(load("levels_exhausted", &levels(r#"{ name = "secondary", selections = 5, dependent_on = "primary", min_value = 1, max_value = 10 }"#)).is_err());
+    assert!(load("levels_disjoint", &levels(r#"{ name = "secondary", selections = 5, dependent_on = "primary", min_value = 11, max_value = 15 }"#)).is_ok());
+    assert!(load("levels_parent", &levels(r#"{ name = "secondary", selections = 1, dependent_on = "bonus", min_value = 1, max_value = 10 }"#)).is_err());
+    assert!(load("levels_range", &with_levels(r#"draw_levels = [{ name = "primary", selections = 1, min_value = 10, max_value = 1 }]"#)).is_err());
+    assert!(load("levels_small", &with_levels(r#"draw_levels = [{ name = "primary", selections = 6, min_value = 1, max_value = 5 }]"#)).is_err());
+}
+
+#[test]
+fn test_schedule_and_exceptions_are_validated() {
+    let game = game_toml("[game]", LOTTO_ID, "Lotto");
+    let with_schedule = |schedule: &str| game.replace(r#"schedule = { daily = { time = "21:50" } }"#, schedule);
+
+    assert!(load("schedule_time", &with_schedule(r#"schedule = { daily = { time = "25:00" } }"#)).is_err());
+    assert!(load("schedule_interval", &with_schedule(r#"schedule = { interval = { minutes = 0 } }"#)).is_err());
+    assert!(load("schedule_weekly", &with_schedule(r#"schedule = { weekly = { days = [] } }"#)).is_err());
+
+    let exception = |exception: &str| format!("{}schedule_exceptions = [{}]\n", game, exception);
+    assert!(load("exception", &exception(r#"{ type = "skip", date = "2026-12-24" }"#)).is_ok());
+    assert!(load("exception_date", &exception(r#"{ type = "skip", date = "2026-12-32" }"#)).is_err());
+    assert!(load("exception_time", &exception(r#"{ type = "move", date = "2026-12-24", time = "9pm", to = "2026-12-23 21:50" }"#)).is_err());
+    assert!(load(
+        "exception_reversed",
+        &exception(r#"{ type = "extra", draw_time = "2026-12-31 20:00", open_time = "2026-12-31 19:00", close_time = "2026-12-31 18:00" }"#)
+    )
+    .is_err());
+}
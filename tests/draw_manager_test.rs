@@ -0,0 +1,110 @@
+mod common;
+
+use chrono::{Duration, TimeZone, Utc};
+use common::{draw_level_configs, draw_levels};
+use rlottery::config::app_config::OpenPolicyConfig;
+use rlottery::core::draw::{DrawStatus, WinningNumbers};
+use rlottery::core::draw_level::DrawLevel;
+use rlottery::core::draw_manager::DrawManager;
+use uuid::Uuid;
+
+#[test]
+fn test_draw_walks_through_full_lifecycle() {
+    let now = Utc::now();
+    let mut draw = DrawManager::new_draw(Uuid::new_v4(), now, now + Duration::hours(1), now + Duration::hours(2));
+
+    for status in [
+        DrawStatus::Open,
+        DrawStatus::Closed,
+        DrawStatus::Drawn,
+        DrawStatus::WinsetCalculated,
+        DrawStatus::WinsetConfirmed,
+        DrawStatus::Finalized,
+    ] {
+        DrawManager::transition_draw_status(&mut draw, status.clone()).expect("Transition should be allowed");
+        assert_eq!(draw.status, status);
+    }
+    assert!(draw.winset_calculated_at.is_some());
+    assert!(draw.winset_confirmed_at.is_some());
+    assert!(DrawManager::transition_draw_status(&mut draw, DrawStatus::Open).is_err());
+}
+
+#[test]
+fn test_only_unfinalized_draws_can_be_cancelled() {
+    let now = Utc::now();
+    let mut draw = DrawManager::new_draw(Uuid::new_v4(), now, now + Duration::hours(1), now + Duration::hours(2));
+    DrawManager::transition_draw_status(&mut draw, DrawStatus::Open).expect("Transition should be allowed");
+    DrawManager::transition_draw_status(&mut draw, DrawStatus::Cancelled).expect("An open draw can be cancelled");
+    assert_eq!(draw.status, DrawStatus::Cancelled);
+    assert!(DrawManager::transition_draw_status(&mut draw, DrawStatus::Cancelled).is_err());
+
+    draw.status = DrawStatus::Finalized;
+    assert!(DrawManager::transition_draw_status(&mut draw, DrawStatus::Cancelled).is_err());
+    assert_eq!(draw.status, DrawStatus::Finalized);
+}
+
+#[test]
+fn test_dependent_draw_level_does_not_repeat_parent_numbers() {
+    let game_id = Uuid::new_v4();
+    let levels = draw_levels(game_id);
+    let now = Utc::now();
+
+    for seed_byte in 0..=255u8 {
+        let mut draw = DrawManager::new_draw(game_id, now, now, now);
+        DrawManager::draw_winning_numbers(&mut draw, &levels, [seed_byte; 64]).unwrap();
+
+        let primary = &draw.winning_numbers[0].numbers;
+        let secondary = &draw.winning_numbers[1].numbers;
+        assert_eq!(primary.len(), 6);
+        assert_eq!(secondary.len(), 1);
+        assert!(primary.iter().chain(secondary).all(|n| (1..=40).contains(n)));
+        assert!(!primary.contains(&secondary[0]), "Secondary number {} repeats a primary number", secondary[0]);
+    }
+}
+
+#[test]
+fn test_drawing_fails_when_numbers_run_out() {
+    let game_id = Uuid::new_v4();
+    let levels: Vec<DrawLevel> = draw_level_configs(6).iter().map(|config| DrawLevel::from_config(game_id, config)).collect();
+    let now = Utc::now();
+    let mut draw = DrawManager::new_draw(game_id, now, now, now);
+
+    // The primary level takes all 6 numbers, leaving none for the secondary level
+    assert!(DrawManager::draw_winning_numbers(&mut draw, &levels, [0; 64]).is_err());
+    assert!(draw.winning_numbers.is_empty());
+}
+
+#[test]
+fn test_external_winning_numbers_are_validated() {
+    let levels = draw_levels(Uuid::new_v4());
+    let numbers = |primary: &[u32], secondary: &[u32]| {
+        vec![
+            WinningNumbers { draw_level_id: levels[0].id, numbers: primary.to_vec() },
+            WinningNumbers { draw_level_id: levels[1].id, numbers: secondary.to_vec() },
+        ]
+    };
+
+    assert!(DrawManager::validate_winning_numbers(&levels, &numbers(&[1, 2, 3, 4, 5, 6], &[7])).is_ok());
+    assert!(DrawManager::validate_winning_numbers(&levels, &numbers(&[1, 2, 3, 4, 5], &[7])).is_err());
+    assert!(DrawManager::validate_winning_numbers(&levels, &numbers(&[1, 2, 3, 4, 5, 41], &[7])).is_err());
+    assert!(DrawManager::validate_winning_numbers(&levels, &numbers(&[1, 2, 3, 4, 5, 5], &[7])).is_err());
+    assert!(DrawManager::validate_winning_numbers(&levels, &numbers(&[1, 2, 3, 4, 5, 6], &[6])).is_err());
+    assert!(DrawManager::validate_winning_numbers(&levels, &numbers(&[1, 2, 3, 4, 5, 6], &[7])[..1]).is_err());
+
+    let mut unknown = numbers(&[1, 2, 3, 4, 5, 6], &[7]);
+    unknown.push(WinningNumbers { draw_level_id: Uuid::new_v4(), numbers: vec![8] });
+    assert!(DrawManager::validate_winning_numbers(&levels, &unknown).is_err());
+}
+
+#[test]
+fn test_open_policy_open_times() {
+    let now = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+    let draw_time = Utc.with_ymd_and_hms(2026, 3, 4, 21, 50, 0).unwrap();
+    let previous_close_time = Utc.with_ymd_and_hms(2026, 3, 3, 21, 45, 0).unwrap();
+    let open_time = |policy| DrawManager::policy_open_time(&policy, now, draw_time, previous_close_time);
+
+    assert_eq!(open_time(OpenPolicyConfig::Immediately), now);
+    assert_eq!(open_time(OpenPolicyConfig::BeforeDraw { hours: 24 }), Utc.with_ymd_and_hms(2026, 3, 3, 21, 50, 0).unwrap());
+    assert_eq!(open_time(OpenPolicyConfig::BeforeDraw { hours: 240 }), now);
+    assert_eq!(open_time(OpenPolicyConfig::AfterPreviousClose), previous_close_time);
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use common::{board, draw_level_configs};
+use rlottery::config::app_config::WagerClassConfig;
+use rlottery::core::board::GameType;
+use rlottery::core::quick_pick::{fill_board, find_wager_class};
+use rlottery::core::rng::Rng;
+
+fn wager_class(name: &str, primary: u32) -> WagerClassConfig {
+    WagerClassConfig {
+        name: name.to_string(),
+        selections: vec!["primary".to_string(), "secondary".to_string()],
+        number_of_selections: vec![primary, 1],
+        stake_min: 100,
+        stake_max: 100,
+        stake_increment: 100,
+    }
+}
+
+#[test]
+fn test_fill_partial_board() {
+    let mut rng = Rng::new([7; 64]);
+    let class = wager_class("normal", 6);
+    let mut board = board(GameType::NORMAL, &[9, 2]);
+
+    fill_board(&mut board, &class, &draw_level_configs(10), &mut rng).unwrap();
+
+    let primary = &board.selections[0];
+    assert_eq!(primary.values.len(), 6);
+    assert!(primary.values.windows(2).all(|w| w[0] < w[1]));
+    assert!(primary.values.iter().all(|v| (1..=10).contains(v)));
+    assert_eq!(primary.machine_picked.iter().filter(|picked| **picked).count(), 4);
+    for (value, picked) in primary.values.iter().zip(&primary.machine_picked) {
+        assert_eq!(*picked, *value != 9 && *value != 2);
+    }
+
+    let secondary = &board.selections[1];
+    assert_eq!(secondary.values.len(), 1);
+    assert_eq!(secondary.machine_picked, vec![true]);
+    assert!(!primary.values.contains(&secondary.values[0]));
+}
+
+#[test]
+fn test_system_size_selects_wager_class() {
+    let classes = vec![wager_class("normal", 6), wager_class("system8", 8)];
+    let mut board = board(GameType::SYSTEM, &[]);
+
+    let class = find_wager_class(&board, 8, &classes).unwrap();
+    assert_eq!(class.name, "system8");
+    assert!(find_wager_class(&board, 9, &classes).is_err());
+
+    fill_board(&mut board, class, &draw_level_configs(10), &mut Rng::new([1; 64])).unwrap();
+    assert_eq!(board.selections[0].values.len(), 8);
+
+    // Filling all 10 primary numbers leaves no number for the dependent level
+    let mut full = board.clone();
+    full.selections.truncate(1);
+    assert!(fill_board(&mut full, &wager_class("system10", 10), &draw_level_configs(10), &mut Rng::new([1; 64])).is_err());
+}
@@ -0,0 +1,227 @@
+mod common;
+
+use chrono::{DateTime, Duration, Utc};
+use common::{board, setup_database, wager};
+use rlottery::config::app_config::RetentionConfig;
+use rlottery::core::board::GameType;
+use rlottery::core::draw::{Draw, DrawStatus};
+use rlottery::core::draw_manager::DrawManager;
+use rlottery::core::extension::Extensions;
+use rlottery::core::retention::{RetentionJob, RetentionSummary};
+use rlottery::db::partition::{partition_name, PARTITIONED_TABLES};
+use rlottery::db::{self, Pool};
+use uuid::Uuid;
+
+async fn insert_game(pool: &Pool) -> Uuid {
+    let client = pool.get().await.unwrap();
+    let game_id = Uuid::new_v4();
+    client
+        .execute("INSERT INTO lottery_operator (id, name) VALUES (1, 'operator') ON CONFLICT DO NOTHING", &[])
+        .await
+        .unwrap();
+    client
+        .execute("INSERT INTO game (id, lottery_operator_id, name) VALUES ($1, 1, 'game')", &[&game_id])
+        .await
+        .unwrap();
+    game_id
+}
+
+/// Inserts a draw in `status` with its partitions, drawn `day` days from now.
+async fn insert_draw(pool: &Pool, game_id: Uuid, day: i64, status: DrawStatus) -> Draw {
+    let client = pool.get().await.unwrap();
+    let now = Utc::now();
+    let mut draw = DrawManager::new_draw(game_id, now, now + Duration::days(day), now + Duration::days(day));
+    draw.status = status;
+    draw.id = db::draw::insert_draw(&**client, &draw).await.unwrap();
+    db::partition::create_draw_partitions(&client, draw.id).await.unwrap();
+    draw
+}
+
+async fn place_wager(pool: &Pool, draws: &[&Draw]) -> Uuid {
+    let mut client = pool.get().await.unwrap();
+    let client: &mut tokio_postgres::Client = &mut client;
+    let mut wager = wager(draws.iter().map(|d| (*d).clone()).collect());
+    let mut board = board(GameType::NORMAL, &[1, 2, 3, 4, 5, 6]);
+    board.wager_id = wager.id;
+    board.stake = wager.stake;
+    wager.boards.push(board);
+    let transaction = client.transaction().await.unwrap();
+    db::wager::insert_wager(&transaction, &wager, draws.iter().map(|d| d.id).collect(), None).await.unwrap();
+    transaction.commit().await.unwrap();
+    wager.id
+}
+
+async fn cancel_draw(pool: &Pool, draw: &Draw) -> u64 {
+    let mut client = pool.get().await.unwrap();
+    let client: &mut tokio_postgres::Client = &mut client;
+    DrawManager::cancel_draw(client, &Extensions::default(), &mut draw.clone()).await.expect("Cancellation failed")
+}
+
+/// Moves a draw to a status at a time in the past, as if it had gone through its lifecycle.
+async fn age_draw(pool: &Pool, draw: &Draw, status: DrawStatus, draw_time: DateTime<Utc>) {
+    let client = pool.get().await.unwrap();
+    client
+        .execute(
+            "UPDATE draw SET status = $2, open_time = $3, close_time = $3, draw_time = $3 WHERE id = $1",
+            &[&draw.id, &status.to_string(), &draw_time],
+        )
+        .await
+        .unwrap();
+}
+
+async fn count(pool: &Pool, query: &str) -> i64 {
+    pool.get().await.unwrap().query_one(query, &[]).await.unwrap().get(0)
+}
+
+/// Number of partitions of a draw attached to their partitioned table.
+async fn attached_partitions(pool: &Pool, draw_id: i32) -> usize {
+    let names: Vec<String> = PARTITIONED_TABLES.iter().map(|table| partition_name(table, draw_id)).collect();
+    let client = pool.get().await.unwrap();
+    let row = client
+        .query_one(
+            "SELECT COUNT(*) FROM pg_inherits i JOIN pg_class c ON c.oid = i.inhrelid WHERE c.relname = ANY($1)",
+            &[&names],
+        )
+        .await
+        .unwrap();
+    row.get::<_, i64>(0) as usize
+}
+
+async fn wager_exists(pool: &Pool, wager_id: Uuid) -> bool {
+    let client = pool.get().await.unwrap();
+    db::wager::get_wager(&client, wager_id).await.unwrap().is_some()
+}
+
+#[tokio::test]
+async fn test_retention_deletes_only_expired_terminal_draws() {
+    let database = setup_database().await;
+    let pool = &database.pool;
+    let game_id = insert_game(pool).await;
+
+    let finalized = &insert_draw(pool, game_id, 1, DrawStatus::Open).await;
+    let cancelled = &insert_draw(pool, game_id, 2, DrawStatus::Open).await;
+    let in_progress = &insert_draw(pool, game_id, 3, DrawStatus::Open).await;
+    let recent = &insert_draw(pool, game_id, 4, DrawStatus::Open).await;
+    place_wager(pool, &[finalized]).await;
+    place_wager(pool, &[finalized, cancelled]).await;
+    place_wager(pool, &[cancelled]).await;
+    let in_progress_wager = place_wager(pool, &[in_progress]).await;
+    let recent_wager = place_wager(pool, &[recent]).await;
+    assert_eq!(cancel_draw(pool, cancelled).await, 2);
+
+    let now = Utc::now();
+    age_draw(pool, finalized, DrawStatus::Finalized, now - Duration::days(40)).await;
+    age_draw(pool, cancelled, DrawStatus::Cancelled, now - Duration::days(39)).await;
+    age_draw(pool, in_progress, DrawStatus::Closed, now - Duration::days(38)).await;
+    age_draw(pool, recent, DrawStatus::Finalized, now - Duration::days(10)).await;
+
+    let config = RetentionConfig { max_age_days: 30, batch_size: 1, ..RetentionConfig::default() };
+    let summary = RetentionJob::run(pool, &config).await.expect("Retention failed");
+    assert_eq!(summary, RetentionSummary { draws: 2, partitions: 8, wagers: 3, refunds: 2 });
+
+    let remaining: Vec<i32> = pool
+        .get()
+        .await
+        .unwrap()
+        .query("SELECT id FROM draw ORDER BY id", &[])
+        .await
+        .unwrap()
+        .iter()
+        .map(|row| row.get("id"))
+        .collect();
+    assert_eq!(remaining, vec![in_progress.id, recent.id]);
+    assert_eq!(count(pool, "SELECT COUNT(*) FROM wager").await, 2);
+    assert_eq!(count(pool, "SELECT COUNT(*) FROM refund").await, 0);
+    let client = pool.get().await.unwrap();
+    for wager_id in [in_progress_wager, recent_wager] {
+        let wager = db::wager::get_wager(&client, wager_id).await.unwrap().expect("Wager is kept");
+        assert_eq!(wager.boards.len(), 1);
+    }
+    drop(client);
+
+    // Nothing else has expired
+    assert_eq!(RetentionJob::run(pool, &config).await, Ok(RetentionSummary::default()));
+}
+
+#[tokio::test]
+async fn test_draw_partitions_are_attached_and_dropped() {
+    let database = setup_database().await;
+    let pool = &database.pool;
+    let game_id = insert_game(pool).await;
+
+    let draw = insert_draw(pool, game_id, 1, DrawStatus::Created).await;
+    assert_eq!(attached_partitions(pool, draw.id).await, 4);
+    // Creating the partitions again is harmless
+    let client = pool.get().await.unwrap();
+    db::partition::create_draw_partitions(&client, draw.id).await.unwrap();
+    assert_eq!(attached_partitions(pool, draw.id).await, 4);
+
+    // A partition created but not attached by an interrupted call is attached
+    let next = DrawManager::new_draw(game_id, Utc::now(), draw.close_time + Duration::days(1), draw.close_time + Duration::days(1));
+    let next_id = db::draw::insert_draw(&**client, &next).await.unwrap();
+    client
+        .batch_execute(&format!("CREATE TABLE {} (LIKE board INCLUDING DEFAULTS)", partition_name("board", next_id)))
+        .await
+        .unwrap();
+    db::partition::create_draw_partitions(&client, next_id).await.unwrap();
+    assert_eq!(attached_partitions(pool, next_id).await, 4);
+
+    // A draw cannot open without its partitions; opening it creates them first
+    let mut later = DrawManager::new_draw(game_id, Utc::now(), next.close_time + Duration::days(1), next.close_time + Duration::days(1));
+    later.id = db::draw::insert_draw(&**client, &later).await.unwrap();
+    let mut opening_client = pool.get().await.unwrap();
+    let opening_client: &mut tokio_postgres::Client = &mut opening_client;
+    let extensions = Extensions::default();
+    assert!(DrawManager::persist_transition(opening_client, &extensions, &mut later.clone(), DrawStatus::Open).await.is_err());
+    DrawManager::open_draw(opening_client, &extensions, &mut later).await.expect("Opening failed");
+    assert_eq!(later.status, DrawStatus::Open);
+    assert_eq!(attached_partitions(pool, later.id).await, 4);
+
+    // Replanning deletes created draws, whose partitions are dropped after the deletion
+    let deleted = db::draw::delete_created_draws(&**client, game_id).await.unwrap();
+    assert_eq!(deleted, vec![draw.id, next_id]);
+    for draw_id in deleted {
+        assert_eq!(db::partition::drop_draw_partitions(&client, draw_id).await.unwrap(), 4);
+        assert_eq!(attached_partitions(pool, draw_id).await, 0);
+        assert_eq!(db::partition::drop_draw_partitions(&client, draw_id).await.unwrap(), 0);
+    }
+}
+
+#[tokio::test]
+async fn test_retention_waits_for_earlier_draws_in_progress() {
+    let database = setup_database().await;
+    let pool = &database.pool;
+    let game_id = insert_game(pool).await;
+
+    let first = &insert_draw(pool, game_id, 1, DrawStatus::Open).await;
+    let cancelled = &insert_draw(pool, game_id, 2, DrawStatus::Open).await;
+    let recent = &insert_draw(pool, game_id, 3, DrawStatus::Open).await;
+    let expiring_wager = place_wager(pool, &[first, cancelled]).await;
+    let continuing_wager = place_wager(pool, &[cancelled, recent]).await;
+    assert_eq!(cancel_draw(pool, cancelled).await, 2);
+
+    let now = Utc::now();
+    age_draw(pool, first, DrawStatus::Closed, now - Duration::days(40)).await;
+    age_draw(pool, cancelled, DrawStatus::Cancelled, now - Duration::days(39)).await;
+    age_draw(pool, recent, DrawStatus::Finalized, now - Duration::days(10)).await;
+
+    // The boards of the first draw's wagers are in the partitions of the cancelled draw
+    let config = RetentionConfig { max_age_days: 30, batch_size: 1, ..RetentionConfig::default() };
+    assert_eq!(RetentionJob::run(pool, &config).await, Ok(RetentionSummary::default()));
+    assert_eq!(attached_partitions(pool, cancelled.id).await, 4);
+    assert!(wager_exists(pool, expiring_wager).await);
+
+    age_draw(pool, first, DrawStatus::Finalized, now - Duration::days(40)).await;
+    let summary = RetentionJob::run(pool, &config).await.expect("Retention failed");
+    assert_eq!(summary, RetentionSummary { draws: 2, partitions: 8, wagers: 1, refunds: 2 });
+    assert_eq!(attached_partitions(pool, first.id).await, 0);
+    assert_eq!(attached_partitions(pool, cancelled.id).await, 0);
+    assert_eq!(attached_partitions(pool, recent.id).await, 4);
+    assert!(!wager_exists(pool, expiring_wager).await);
+
+    // A wager continuing past the deleted draws keeps its boards in the partitions of its last draw
+    let client = pool.get().await.unwrap();
+    let wager = db::wager::get_wager(&client, continuing_wager).await.unwrap().expect("Wager is kept");
+    assert_eq!(wager.draws.iter().map(|d| d.id).collect::<Vec<_>>(), vec![recent.id]);
+    assert_eq!(wager.boards.len(), 1);
+}
@@ -0,0 +1,94 @@
+use chrono::{TimeZone, Utc};
+use chrono_tz::Tz;
+use rlottery::config::app_config::{ScheduleConfig, ScheduleExceptionConfig, WeekdayScheduleConfig};
+use rlottery::core::schedule::{next_draw_time, next_planned_draw, validate_exception};
+
+#[test]
+fn test_weekly_schedule_with_several_times_per_day() {
+    let schedule = ScheduleConfig::Weekly {
+        days: vec![
+            WeekdayScheduleConfig { day: "Tuesday".to_string(), times: vec!["21:30".to_string(), "17:30".to_string()] },
+            WeekdayScheduleConfig { day: "Fri".to_string(), times: vec!["22:00".to_string()] },
+        ],
+    };
+    let helsinki: Tz = "Europe/Helsinki".parse().unwrap();
+
+    // Monday 2026-01-05 12:00 local, UTC+2 in winter
+    let mut time = helsinki.with_ymd_and_hms(2026, 1, 5, 12, 0, 0).unwrap().with_timezone(&Utc);
+    let mut draws = Vec::new();
+    for _ in 0..4 {
+        time = next_draw_time(&schedule, helsinki, time).unwrap();
+        draws.push(time);
+    }
+    assert_eq!(draws, vec![
+        Utc.with_ymd_and_hms(2026, 1, 6, 15, 30, 0).unwrap(),
+        Utc.with_ymd_and_hms(2026, 1, 6, 19, 30, 0).unwrap(),
+        Utc.with_ymd_and_hms(2026, 1, 9, 20, 0, 0).unwrap(),
+        Utc.with_ymd_and_hms(2026, 1, 13, 15, 30, 0).unwrap(),
+    ]);
+
+    let invalid = ScheduleConfig::Weekly {
+        days: vec![WeekdayScheduleConfig { day: "Someday".to_string(), times: vec!["17:30".to_string()] }],
+    };
+    assert!(next_draw_time(&invalid, helsinki, time).is_err());
+}
+
+#[test]
+fn test_daily_schedule_keeps_local_time_across_dst_and_interval_schedule() {
+    let daily = ScheduleConfig::Daily { time: "21:50".to_string() };
+    let helsinki: Tz = "Europe/Helsinki".parse().unwrap();
+
+    // Clocks move forward on 2026-03-29 in Helsinki, from UTC+2 to UTC+3
+    let before = next_draw_time(&daily, helsinki, Utc.with_ymd_and_hms(2026, 3, 28, 12, 0, 0).unwrap()).unwrap();
+    let after = next_draw_time(&daily, helsinki, before).unwrap();
+    assert_eq!(before, Utc.with_ymd_and_hms(2026, 3, 28, 19, 50, 0).unwrap());
+    assert_eq!(after, Utc.with_ymd_and_hms(2026, 3, 29, 18, 50, 0).unwrap());
+
+    let interval = ScheduleConfig::Interval { minutes: 15 };
+    let start = Utc.with_ymd_and_hms(2026, 3, 28, 12, 7, 30).unwrap();
+    let first = next_draw_time(&interval, helsinki, start).unwrap();
+    assert_eq!(first, Utc.with_ymd_and_hms(2026, 3, 28, 12, 15, 0).unwrap());
+    assert_eq!(next_draw_time(&interval, helsinki, first).unwrap(), Utc.with_ymd_and_hms(2026, 3, 28, 12, 30, 0).unwrap());
+    assert!(next_draw_time(&ScheduleConfig::Interval { minutes: 0 }, helsinki, start).is_err());
+}
+
+#[test]
+fn test_schedule_exceptions_skip_move_and_add_draws() {
+    let daily = ScheduleConfig::Daily { time: "21:50".to_string() };
+    let exceptions = vec![
+        ScheduleExceptionConfig::Skip { date: "2026-12-24".to_string(), time: None },
+        ScheduleExceptionConfig::Move { date: "2026-12-25".to_string(), time: Some("21:50".to_string()), to: "2026-12-26 12:00".to_string() },
+        ScheduleExceptionConfig::Extra {
+            draw_time: "2026-12-26 18:00".to_string(),
+            open_time: Some("2026-12-20 09:00".to_string()),
+            close_time: Some("2026-12-26 17:00".to_string()),
+        },
+    ];
+
+    let mut after = Utc.with_ymd_and_hms(2026, 12, 23, 22, 0, 0).unwrap();
+    let mut draws = Vec::new();
+    for _ in 0..4 {
+        let planned = next_planned_draw(&daily, Tz::UTC, &exceptions, after).unwrap();
+        after = planned.draw_time;
+        draws.push(planned);
+    }
+    let draw_times: Vec<_> = draws.iter().map(|d| d.draw_time).collect();
+    assert_eq!(draw_times, vec![
+        Utc.with_ymd_and_hms(2026, 12, 26, 12, 0, 0).unwrap(),
+        Utc.with_ymd_and_hms(2026, 12, 26, 18, 0, 0).unwrap(),
+        Utc.with_ymd_and_hms(2026, 12, 26, 21, 50, 0).unwrap(),
+        Utc.with_ymd_and_hms(2026, 12, 27, 21, 50, 0).unwrap(),
+    ]);
+    assert_eq!(draws[1].open_time, Some(Utc.with_ymd_and_hms(2026, 12, 20, 9, 0, 0).unwrap()));
+    assert_eq!(draws[1].close_time, Some(Utc.with_ymd_and_hms(2026, 12, 26, 17, 0, 0).unwrap()));
+    assert_eq!(draws[0].close_time, None);
+
+    assert!(exceptions.iter().all(|e| validate_exception(e, Tz::UTC).is_ok()));
+    let closes_after_draw = ScheduleExceptionConfig::Extra {
+        draw_time: "2026-12-26 18:00".to_string(),
+        open_time: None,
+        close_time: Some("2026-12-26 19:00".to_string()),
+    };
+    assert!(validate_exception(&closes_after_draw, Tz::UTC).is_err());
+    assert!(validate_exception(&ScheduleExceptionConfig::Skip { date: "24.12.2026".to_string(), time: None }, Tz::UTC).is_err());
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use common::{board, draw_levels};
+use rlottery::config::app_config::WagerClassConfig;
+use rlottery::core::board::GameType;
+use rlottery::core::system_wager::{board_stake, expand_rows, find_wager_class, row_count, wager_price};
+use uuid::Uuid;
+
+fn wager_class(name: &str, numbers: u32, stake_min: u32, stake_max: u32, stake_increment: u32) -> WagerClassConfig {
+    WagerClassConfig {
+        name: name.to_string(),
+        selections: vec!["primary".to_string()],
+        number_of_selections: vec![numbers],
+        stake_min,
+        stake_max,
+        stake_increment,
+    }
+}
+
+#[test]
+fn test_system_board_expands_into_all_combinations() {
+    let levels = draw_levels(Uuid::new_v4());
+
+    let normal = board(GameType::NORMAL, &[1, 2, 3, 4, 5, 6]);
+    assert_eq!(row_count(&normal, &levels), 1);
+    assert_eq!(expand_rows(&normal, &levels)[0][0].values, vec![1, 2, 3, 4, 5, 6]);
+
+    let system = board(GameType::SYSTEM, &[1, 2, 3, 4, 5, 6, 7, 8]);
+    let rows = expand_rows(&system, &levels);
+    assert_eq!(row_count(&system, &levels), 28);
+    assert_eq!(rows.len(), 28);
+    assert!(rows.iter().all(|row| row[0].values.len() == 6));
+    assert_eq!(rows[0][0].values, vec![1, 2, 3, 4, 5, 6]);
+    assert_eq!(rows[27][0].values, vec![3, 4, 5, 6, 7, 8]);
+}
+
+#[test]
+fn test_stake_and_price() {
+    let classes = vec![wager_class("normal", 6, 100, 1000, 50), wager_class("system8", 8, 2800, 2800, 2800)];
+
+    let normal = find_wager_class(&board(GameType::NORMAL, &[1, 2, 3, 4, 5, 6]), &classes).unwrap();
+    assert_eq!(board_stake(normal, 0), Ok(100));
+    assert_eq!(board_stake(normal, 250), Ok(250));
+    assert!(board_stake(normal, 260).is_err());
+    assert!(board_stake(normal, 50).is_err());
+    assert!(board_stake(normal, 1050).is_err());
+
+    let system = find_wager_class(&board(GameType::SYSTEM, &[1, 2, 3, 4, 5, 6, 7, 8]), &classes).unwrap();
+    assert_eq!(board_stake(system, 0), Ok(2800));
+    assert!(find_wager_class(&board(GameType::SYSTEM, &[1, 2, 3, 4, 5, 6, 7]), &classes).is_err());
+
+    assert_eq!(wager_price(2900, 3), Ok(8700));
+    assert!(wager_price(u32::MAX, 2).is_err());
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use common::{draw_level_configs, draws, selection, wager};
+use rlottery::config::app_config::WagerClassConfig;
+use chrono::{Duration, Utc};
+use rlottery::core::board::{Board, GameType};
+use rlottery::core::draw::{Draw, DrawStatus};
+use rlottery::core::draw_manager::DrawManager;
+use rlottery::core::refund::wager_refunds;
+use rlottery::core::validation::{validate_board, validate_participation, validate_wager_cancellation};
+use rlottery::core::wager::WagerStatus;
+use uuid::Uuid;
+
+fn normal_class() -> WagerClassConfig {
+    WagerClassConfig {
+        name: "normal".to_string(),
+        selections: vec!["primary".to_string(), "secondary".to_string()],
+        number_of_selections: vec![6, 1],
+        stake_min: 100,
+        stake_max: 100,
+        stake_increment: 100,
+    }
+}
+
+fn board(primary: &[i32], secondary: &[i32]) -> Board {
+    let mut board = common::board(GameType::NORMAL, primary);
+    board.selections.push(selection("secondary", secondary));
+    board
+}
+
+#[test]
+fn test_valid_board_is_canonicalised() {
+    let mut board = board(&[40, 3, 17, 1, 9, 22], &[5]);
+    assert_eq!(validate_board(&mut board, &normal_class(), &draw_level_configs(40)), Ok(()));
+    assert_eq!(board.selections[0].values, vec![1, 3, 9, 17, 22, 40]);
+}
+
+#[test]
+fn test_invalid_values_are_rejected() {
+    let class = normal_class();
+    let levels = draw_level_configs(40);
+
+    assert!(validate_board(&mut board(&[0, 0, 99, -5, 7, 7], &[5]), &class, &levels).is_err());
+    assert!(validate_board(&mut board(&[1, 2, 3, 4, 5, 41], &[6]), &class, &levels).is_err());
+    assert!(validate_board(&mut board(&[1, 2, 3, 4, 7, 7], &[6]), &class, &levels).is_err());
+    assert!(validate_board(&mut board(&[1, 2, 3, 4, 5, 6], &[6]), &class, &levels).is_err());
+    assert!(validate_board(&mut board(&[1, 2, 3, 4, 5], &[6]), &class, &levels).is_err());
+}
+
+#[test]
+fn test_participation_requires_consecutive_draws_from_the_first() {
+    let open_draws = draws(&[(3, 1), (1, 2), (2, 3), (4, 4)]);
+    let allowed = [1, 2, 3];
+    let ids = |draws: Vec<Draw>| draws.iter().map(|d| d.id).collect::<Vec<_>>();
+
+    assert_eq!(ids(validate_participation(&open_draws, &[1, 3], &allowed, false).unwrap()), vec![3, 1]);
+    assert_eq!(ids(validate_participation(&open_draws, &[3], &allowed, false).unwrap()), vec![3]);
+    assert!(validate_participation(&open_draws, &[3, 2], &allowed, false).is_err());
+    assert!(validate_participation(&open_draws, &[3, 1, 2, 4], &allowed, false).is_err());
+    assert!(validate_participation(&open_draws, &[3, 3], &allowed, false).is_err());
+    assert!(validate_participation(&open_draws, &[], &allowed, false).is_err());
+
+    assert!(validate_participation(&open_draws, &[1, 2], &allowed, false).is_err());
+    assert_eq!(ids(validate_participation(&open_draws, &[1, 2], &allowed, true).unwrap()), vec![1, 2]);
+    assert!(validate_participation(&open_draws, &[2, 4], &allowed, true).is_err());
+}
+
+#[test]
+fn test_wager_cancellation_window_and_refunds() {
+    let now = Utc::now();
+    let draws: Vec<Draw> = (1..=2)
+        .map(|id| {
+            let mut draw = DrawManager::new_draw(Uuid::new_v4(), now, now + Duration::hours(id), now + Duration::hours(id));
+            draw.id = id as i32;
+            draw.status = DrawStatus::Open;
+            draw
+        })
+        .collect();
+    let mut wager = wager(draws.clone());
+    wager.created_at = now - Duration::minutes(5);
+
+    assert!(validate_wager_cancellation(&wager, &draws, 10, now).is_ok());
+    assert!(validate_wager_cancellation(&wager, &draws, 0, now).is_err());
+    assert!(validate_wager_cancellation(&wager, &draws, 4, now).is_err());
+    assert!(validate_wager_cancellation(&wager, &draws, 10, now + Duration::hours(1)).is_err());
+    let mut closed = draws.clone();
+    closed[1].status = DrawStatus::Closed;
+    assert!(validate_wager_cancellation(&wager, &closed, 10, now).is_err());
+
+    let refunds = wager_refunds(&wager);
+    assert_eq!(refunds.iter().map(|r| (r.draw_id, r.amount)).collect::<Vec<_>>(), vec![(1, 100), (2, 100)]);
+    assert_eq!(refunds.iter().map(|r| r.amount).sum::<u64>(), wager.price as u64);
+
+    wager.status = WagerStatus::Cancelled;
+    assert!(validate_wager_cancellation(&wager, &draws, 10, now).is_err());
+}
@@ -0,0 +1,11 @@
+mod common;
+
+use common::{draws, wager};
+
+#[test]
+fn test_wager_last_draw_is_latest_by_draw_time() {
+    let mut wager = wager(draws(&[(7, 2), (5, 3), (9, 1)]));
+    assert_eq!(wager.last_draw_id(), Some(5));
+    wager.draws.clear();
+    assert_eq!(wager.last_draw_id(), None);
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use common::draw_level_configs;
+use rlottery::config::app_config::{WinClassConfig, WinClassTypeConfig};
+use rlottery::core::win_class::{RequiredHits, WinClass, WinClassType};
+use uuid::Uuid;
+
+fn win_class_config(r#type: WinClassTypeConfig, draw_levels: &[&str], hits: &[u32]) -> WinClassConfig {
+    WinClassConfig {
+        name: "5+1".to_string(),
+        r#type,
+        draw_levels: draw_levels.iter().map(|l| l.to_string()).collect(),
+        hits: hits.to_vec(),
+        factor: None,
+        constant: Some(500000),
+        percentage: None,
+        min_cap: None,
+        max_cap: None,
+    }
+}
+
+#[test]
+fn test_win_class_from_config() {
+    let game_id = Uuid::new_v4();
+    let config = win_class_config(WinClassTypeConfig::Constant, &["primary", "secondary"], &[5, 1]);
+
+    let win_class = WinClass::from_config(game_id, 1, &config, &draw_level_configs(40)).expect("Valid win class");
+    assert_eq!(win_class.r#type, WinClassType::Constant);
+    assert_eq!(win_class.rank, 1);
+    assert_eq!(win_class.required_hits, vec![
+        RequiredHits { draw_level: "primary".to_string(), hits: 5 },
+        RequiredHits { draw_level: "secondary".to_string(), hits: 1 },
+    ]);
+    // Ids are stable across restarts
+    assert_eq!(win_class.id, WinClass::from_config(game_id, 1, &config, &draw_level_configs(40)).unwrap().id);
+}
+
+#[test]
+fn test_invalid_win_class_config_is_rejected() {
+    let game_id = Uuid::new_v4();
+    let levels = draw_level_configs(40);
+
+    let unknown_level = win_class_config(WinClassTypeConfig::Constant, &["tertiary"], &[1]);
+    assert!(WinClass::from_config(game_id, 0, &unknown_level, &levels).is_err());
+
+    let mismatched_hits = win_class_config(WinClassTypeConfig::Constant, &["primary", "secondary"], &[5]);
+    assert!(WinClass::from_config(game_id, 0, &mismatched_hits, &levels).is_err());
+
+    let too_many_hits = win_class_config(WinClassTypeConfig::Constant, &["primary"], &[7]);
+    assert!(WinClass::from_config(game_id, 0, &too_many_hits, &levels).is_err());
+
+    let missing_factor = win_class_config(WinClassTypeConfig::Factor, &["primary"], &[4]);
+    assert!(WinClass::from_config(game_id, 0, &missing_factor, &levels).is_err());
+}
@@ -0,0 +1,82 @@
+use chrono::Utc;
+use rlottery::core::draw::DrawStatus;
+use rlottery::core::draw_manager::DrawManager;
+use rlottery::core::win_class::{WinClass, WinClassType};
+use rlottery::core::win_sum::{amount_per_row, class_payout, validate_external_total, ClassPayout, PayoutBasis};
+use uuid::Uuid;
+
+fn win_class(r#type: WinClassType, min_cap: Option<u64>, max_cap: Option<u64>) -> WinClass {
+    WinClass {
+        id: Uuid::new_v4(),
+        game_id: Uuid::new_v4(),
+        name: "test".to_string(),
+        r#type,
+        factor: Some(5),
+        constant: Some(1000),
+        percentage: Some(40),
+        min_cap,
+        max_cap,
+        rank: 0,
+        required_hits: Vec::new(),
+    }
+}
+
+#[test]
+fn test_uncapped_payouts() {
+    let factor = win_class(WinClassType::Factor, None, None);
+    assert_eq!(class_payout(&factor, 3, 300, 10_000, None), Some(ClassPayout { total: 1500, basis: PayoutBasis::ByStake }));
+
+    let constant = win_class(WinClassType::Constant, None, None);
+    assert_eq!(class_payout(&constant, 3, 300, 10_000, None), Some(ClassPayout { total: 3000, basis: PayoutBasis::PerRow }));
+
+    let percentage = win_class(WinClassType::Percentage, None, None);
+    assert_eq!(class_payout(&percentage, 3, 300, 10_000, None), Some(ClassPayout { total: 4000, basis: PayoutBasis::PerRow }));
+
+    let external = win_class(WinClassType::External, None, None);
+    assert_eq!(class_payout(&external, 3, 300, 10_000, None), None);
+    assert_eq!(class_payout(&external, 3, 300, 10_000, Some(777)), Some(ClassPayout { total: 777, basis: PayoutBasis::PerRow }));
+}
+
+#[test]
+fn test_capped_payouts_are_shared_and_rounded_down() {
+    let min_capped = win_class(WinClassType::Percentage, Some(10_000), None);
+    let payout = class_payout(&min_capped, 3, 300, 10_000, None).unwrap();
+    assert_eq!(payout.total, 10_000);
+    assert_eq!(amount_per_row(&payout, 3), 3333);
+
+    let max_capped = win_class(WinClassType::Constant, None, Some(2500));
+    let payout = class_payout(&max_capped, 3, 300, 10_000, None).unwrap();
+    assert_eq!(payout.total, 2500);
+    assert_eq!(amount_per_row(&payout, 3), 833);
+
+    // Caps apply to the class total only when the class has winners
+    assert_eq!(class_payout(&min_capped, 0, 0, 10_000, None).unwrap().total, 0);
+}
+
+#[test]
+fn test_external_class_waits_for_its_total_before_confirming() {
+    let external = win_class(WinClassType::External, None, None);
+    let now = Utc::now();
+    let mut draw = DrawManager::new_draw(external.game_id, now, now, now);
+    for status in [DrawStatus::Open, DrawStatus::Closed, DrawStatus::Drawn, DrawStatus::WinsetCalculated] {
+        DrawManager::transition_draw_status(&mut draw, status).unwrap();
+    }
+
+    // Without a total the draw waits in WinsetCalculated
+    assert_eq!(class_payout(&external, 3, 300, 10_000, None), None);
+
+    // Once the total is set, the win sums are calculated and the winset can be confirmed
+    assert!(validate_external_total(&draw, &external).is_ok());
+    let payout = class_payout(&external, 3, 300, 10_000, Some(1000)).unwrap();
+    assert_eq!(amount_per_row(&payout, 3), 333);
+    DrawManager::transition_draw_status(&mut draw, DrawStatus::WinsetConfirmed).unwrap();
+
+    // Totals can only be set for external classes of draws still waiting for them
+    assert!(validate_external_total(&draw, &external).is_err());
+    let mut calculated = draw.clone();
+    calculated.status = DrawStatus::WinsetCalculated;
+    let constant = WinClass { game_id: draw.game_id, ..win_class(WinClassType::Constant, None, None) };
+    assert!(validate_external_total(&calculated, &constant).is_err());
+    let other_game = win_class(WinClassType::External, None, None);
+    assert!(validate_external_total(&calculated, &other_game).is_err());
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use common::{board, draw_levels, selection};
+use rlottery::core::board::GameType;
+use rlottery::core::draw::WinningNumbers;
+use rlottery::core::win_class::{RequiredHits, WinClass, WinClassType};
+use rlottery::core::winset::WinsetRules;
+use uuid::Uuid;
+
+fn win_class(game_id: Uuid, name: &str, rank: i32, hits: &[(&str, u32)]) -> WinClass {
+    WinClass {
+        id: Uuid::new_v4(),
+        game_id,
+        name: name.to_string(),
+        r#type: WinClassType::Constant,
+        factor: None,
+        constant: Some(1000),
+        percentage: None,
+        min_cap: None,
+        max_cap: None,
+        rank,
+        required_hits: hits
+            .iter()
+            .map(|(level, hits)| RequiredHits { draw_level: level.to_string(), hits: *hits })
+            .collect(),
+    }
+}
+
+fn rules(game_id: Uuid) -> (WinsetRules, Vec<WinClass>) {
+    let levels = draw_levels(game_id);
+    let winning_numbers = vec![
+        WinningNumbers { draw_level_id: levels[0].id, numbers: vec![1, 2, 3, 4, 5, 6] },
+        WinningNumbers { draw_level_id: levels[1].id, numbers: vec![7] },
+    ];
+    let classes = vec![
+        win_class(game_id, "6", 1, &[("primary", 6)]),
+        win_class(game_id, "5+1", 2, &[("primary", 5), ("secondary", 1)]),
+        win_class(game_id, "5", 3, &[("primary", 5)]),
+        win_class(game_id, "4", 4, &[("primary", 4)]),
+    ];
+    (WinsetRules::new(&levels, &winning_numbers, &classes).expect("Valid rules"), classes)
+}
+
+#[test]
+fn test_normal_board_wins_in_first_matching_class() {
+    let game_id = Uuid::new_v4();
+    let (rules, classes) = rules(game_id);
+
+    assert_eq!(rules.board_wins(&board(GameType::NORMAL, &[1, 2, 3, 4, 5, 6])), vec![(classes[0].id, 1)]);
+    assert_eq!(rules.board_wins(&board(GameType::NORMAL, &[1, 2, 3, 4, 5, 7])), vec![(classes[1].id, 1)]);
+    assert_eq!(rules.board_wins(&board(GameType::NORMAL, &[1, 2, 3, 4, 5, 8])), vec![(classes[2].id, 1)]);
+    assert!(rules.board_wins(&board(GameType::NORMAL, &[1, 2, 3, 10, 11, 12])).is_empty());
+}
+
+#[test]
+fn test_system_board_wins_with_every_winning_row() {
+    let game_id = Uuid::new_v4();
+    let (rules, classes) = rules(game_id);
+
+    // 8 numbers make 28 rows: one with 6 hits, 6 with 5+1, 6 with 5 and 15 with 4
+    let wins = rules.board_wins(&board(GameType::SYSTEM, &[1, 2, 3, 4, 5, 6, 7, 8]));
+    let rows = |class: &WinClass| wins.iter().find(|(id, _)| *id == class.id).map(|(_, rows)| *rows);
+    assert_eq!(rows(&classes[0]), Some(1));
+    assert_eq!(rows(&classes[1]), Some(6));
+    assert_eq!(rows(&classes[2]), Some(6));
+    assert_eq!(rows(&classes[3]), Some(15));
+}
+
+#[test]
+fn test_secondary_selection_of_board_is_matched_on_its_own() {
+    let game_id = Uuid::new_v4();
+    let (rules, classes) = rules(game_id);
+
+    let with_secondary = |primary: &[i32], secondary: i32| {
+        let mut board = board(GameType::NORMAL, primary);
+        board.selections.push(selection("secondary", &[secondary]));
+        board
+    };
+
+    assert_eq!(rules.board_wins(&with_secondary(&[1, 2, 3, 4, 5, 8], 7)), vec![(classes[1].id, 1)]);
+    // The secondary number only hits through the board's own secondary selection
+    assert_eq!(rules.board_wins(&with_secondary(&[1, 2, 3, 4, 5, 7], 9)), vec![(classes[2].id, 1)]);
+}